@@ -1,11 +1,64 @@
-use std::io::{BufReader, BufWriter, Seek};
+use std::io::{BufReader, BufWriter, Seek, Write};
 use std::path::PathBuf;
 use std::{fs::File, path::Path};
 
 use argh::FromArgs;
+use tower_suitebro::bom::{self, InventoryFields};
+use tower_suitebro::compact::{self, ClassDefaults, LearnOptions};
+use tower_suitebro::convert::{self, Format};
+use tower_suitebro::dedupe;
+use tower_suitebro::defaults::{self, Defaults};
+use tower_suitebro::diff::{self, DiffOptions};
+use tower_suitebro::euler::{self, RotationStyle};
+use tower_suitebro::explode;
+use tower_suitebro::json_schema;
+use tower_suitebro::lint::{self, LintConfig, Severity};
+use tower_suitebro::lossless;
+use tower_suitebro::merge::{self, MergeOptions};
+use tower_suitebro::property_path::{self, PropertyPath};
+use tower_suitebro::recolor::{self, PaletteEntry, Recolor, Rgba};
+use tower_suitebro::replace::{self, ClassMapping};
+use tower_suitebro::roundtrip;
+use tower_suitebro::sanitize::{self, NonFinite, SanitizeOptions};
+use tower_suitebro::simple::{self, SimpleSchema, Style};
+use tower_suitebro::size;
+use tower_suitebro::stats;
 use tower_suitebro::suitebro::{get_tower_types, SuiteBro};
+use tower_suitebro::textconv;
+use tower_suitebro::transform::Tolerance;
+use tower_suitebro::validate;
+use tower_suitebro::workshop::{self, InstalledWorkshop, TOWER_UNITE_APP_ID};
 use uesave::{Readable, SeekReader, Writable};
 
+pub fn read_save(input: &Path) -> anyhow::Result<SuiteBro> {
+    let input_file = File::open(input)?;
+    let mut reader = BufReader::new(input_file);
+    let mut reader = SeekReader::new(&mut reader);
+    let save = uesave::Context::run_with_types(&get_tower_types(), &mut reader, SuiteBro::read)
+        .map_err(|e| uesave::ParseError {
+            offset: reader.stream_position().unwrap() as usize, // our own implemenation which cannot fail
+            error: e,
+        })?;
+    Ok(save)
+}
+
+pub fn create_output(output: &Path, overwrite: bool) -> anyhow::Result<File> {
+    Ok(match overwrite {
+        true => File::create(output)?,
+        false => File::create_new(output)?,
+    })
+}
+
+pub fn write_save(save: &SuiteBro, output: &Path, overwrite: bool) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(create_output(output, overwrite)?);
+    uesave::Context::run_with_types(&get_tower_types(), &mut writer, |ctx| save.write(ctx))
+        .map_err(|e| uesave::ParseError {
+            offset: writer.stream_position().unwrap() as usize, // our own implemenation which cannot fail
+            error: e,
+        })?;
+    Ok(())
+}
+
 #[derive(FromArgs, PartialEq, Debug)]
 /// Convert a save file to json
 #[argh(subcommand, name = "to-json")]
@@ -21,25 +74,54 @@ pub struct ToJSONArgs {
     /// overwrite the output file?
     #[argh(switch, short = '!')]
     overwrite: bool,
+
+    /// write NaN, infinite and other floats json can't hold exactly as tagged bit patterns
+    #[argh(switch)]
+    lossless: bool,
+
+    /// tagged (uesave's layout) or simple (plain values, with a .schema.json next to the output)
+    #[argh(option, default = "Style::Tagged")]
+    style: Style,
+
+    /// quat (exact) or euler (pitch, yaw and roll in degrees, to-save reads either)
+    #[argh(option, default = "RotationStyle::Quat")]
+    rotation: RotationStyle,
 }
 
-pub fn to_json(input: &Path, output: &Path, overwrite: bool) -> anyhow::Result<()> {
-    let input_file = File::open(&input)?;
-    let mut reader = BufReader::new(input_file);
-    let mut reader = SeekReader::new(&mut reader);
-    let save = uesave::Context::run_with_types(&get_tower_types(), &mut reader, SuiteBro::read)
-        .map_err(|e| uesave::ParseError {
-            offset: reader.stream_position().unwrap() as usize, // our own implemenation which cannot fail
-            error: e,
-        })?;
+pub fn to_json(args: &ToJSONArgs) -> anyhow::Result<()> {
+    let save = read_save(&args.input)?;
+
+    if args.style == Style::Tagged && args.rotation == RotationStyle::Quat {
+        let writer = BufWriter::new(create_output(&args.output, args.overwrite)?);
+        match args.lossless {
+            true => lossless::to_writer_pretty(writer, &save)?,
+            false => serde_json::to_writer_pretty(writer, &save)?,
+        }
+        return Ok(());
+    }
 
-    let output_file = match overwrite {
-        true => File::create(&output)?,
-        false => File::create_new(&output)?,
+    let mut json = match args.lossless {
+        true => lossless::to_value(&save)?,
+        false => serde_json::to_value(&save)?,
     };
-    let writer = BufWriter::new(output_file);
+    if args.rotation == RotationStyle::Euler {
+        euler::to_euler_json(&mut json);
+    }
+
+    if args.style == Style::Simple {
+        let schema_path = args.output.with_extension("schema.json");
+        let schema_file = schema_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow::anyhow!("output needs a utf-8 file name"))?;
+        let (simple, schema) = simple::simplify_json(json, schema_file)?;
+        json = simple;
+        let writer = BufWriter::new(create_output(&schema_path, args.overwrite)?);
+        serde_json::to_writer_pretty(writer, &schema)?;
+    }
 
-    serde_json::to_writer_pretty(writer, &save)?;
+    let writer = BufWriter::new(create_output(&args.output, args.overwrite)?);
+    serde_json::to_writer_pretty(writer, &json)?;
 
     Ok(())
 }
@@ -48,7 +130,7 @@ pub fn to_json(input: &Path, output: &Path, overwrite: bool) -> anyhow::Result<(
 /// Convert json to a save file
 #[argh(subcommand, name = "to-save")]
 pub struct ToSaveArgs {
-    /// json to convert to save file
+    /// json to convert to save file, or any other `convert` format going by the extension
     #[argh(option, short = 'i')]
     input: PathBuf,
 
@@ -59,26 +141,49 @@ pub struct ToSaveArgs {
     /// overwrite the output file?
     #[argh(switch, short = '!')]
     overwrite: bool,
-}
 
-pub fn from_json(input: &Path, output: &Path, overwrite: bool) -> anyhow::Result<()> {
-    let input_file = File::open(&input)?;
-    let reader = BufReader::new(input_file);
-    let save: SuiteBro = serde_json::from_reader(reader)?;
+    /// read json written by `to-json --lossless`
+    #[argh(switch)]
+    lossless: bool,
+
+    /// format version written when the json has no header
+    #[argh(option, default = "Defaults::default().format_version")]
+    format_version: u32,
+
+    /// unreal version written when the json has no header
+    #[argh(option, default = "Defaults::default().unreal_version")]
+    unreal_version: u32,
+}
 
-    let output_file = match overwrite {
-        true => File::create(&output)?,
-        false => File::create_new(&output)?,
+pub fn from_json(args: &ToSaveArgs) -> anyhow::Result<()> {
+    let format = match Format::from_path(&args.input) {
+        Format::Save => Format::Json,
+        format => format,
     };
+    let input = std::fs::read(&args.input)?;
+    // a whole save already, with every float kept
+    if format.is_binary() {
+        let save = convert::parse_save(format, &input)?;
+        return write_save(&save, &args.output, args.overwrite);
+    }
+    let mut json = convert::parse(format, &input)?;
 
-    let mut writer = BufWriter::new(output_file);
-    uesave::Context::run_with_types(&get_tower_types(), &mut writer, |ctx| save.write(ctx))
-        .map_err(|e| uesave::ParseError {
-            offset: writer.stream_position().unwrap() as usize, // our own implemenation which cannot fail
-            error: e,
-        })?;
+    // written by `to-json --style simple`
+    if let Some(schema_file) = simple::schema_file(&json) {
+        let schema_path = args.input.with_file_name(schema_file);
+        let schema_reader = BufReader::new(File::open(&schema_path)?);
+        let schema: SimpleSchema = serde_json::from_reader(schema_reader)?;
+        json = simple::restore_json(json, &schema)?;
+    }
 
-    Ok(())
+    let defaults = Defaults {
+        format_version: args.format_version,
+        unreal_version: args.unreal_version,
+    };
+    defaults::fill_defaults(&mut json, &defaults);
+
+    let save = validate::from_value(json, args.lossless)?;
+    write_save(&save, &args.output, args.overwrite)
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -88,17 +193,1044 @@ pub struct CheckArgs {
     /// save file to check
     #[argh(option, short = 'i')]
     input: PathBuf,
+
+    /// also write the save back in memory and compare the bytes
+    #[argh(switch)]
+    roundtrip: bool,
+
+    /// go through json when writing it back, needs --roundtrip
+    #[argh(switch)]
+    via_json: bool,
+
+    /// use the lossless json encoding, needs --via-json
+    #[argh(switch)]
+    lossless: bool,
 }
 
-pub fn check(input: &Path) -> anyhow::Result<()> {
-    let input_file = File::open(input)?;
-    let mut reader = BufReader::new(input_file);
-    let mut reader = SeekReader::new(&mut reader);
-    let _ = uesave::Context::run_with_types(&get_tower_types(), &mut reader, SuiteBro::read)
-        .map_err(|e| uesave::ParseError {
-            offset: reader.stream_position().unwrap() as usize, // our own implemenation which cannot fail
-            error: e,
-        })?;
+pub fn check(args: &CheckArgs) -> anyhow::Result<()> {
+    if args.via_json && !args.roundtrip {
+        return Err(anyhow::anyhow!("--via-json only applies with --roundtrip"));
+    }
+    if args.lossless && !args.via_json {
+        return Err(anyhow::anyhow!(
+            "--lossless only applies with --roundtrip --via-json"
+        ));
+    }
+    if !args.roundtrip {
+        let _ = read_save(&args.input)?;
+        return Ok(());
+    }
+
+    let mode = match (args.via_json, args.lossless) {
+        (false, _) => roundtrip::Mode::Binary,
+        (true, false) => roundtrip::Mode::Json,
+        (true, true) => roundtrip::Mode::LosslessJson,
+    };
+    match roundtrip::round_trip(&std::fs::read(&args.input)?, mode)? {
+        None => Ok(()),
+        Some(mismatch) => Err(anyhow::anyhow!("{mismatch}")),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Find items of the same class stacked on top of each other
+#[argh(subcommand, name = "dedupe")]
+pub struct DedupeArgs {
+    /// save file to search for duplicates
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// where to write the save with the redundant copies removed
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// max distance between positions in unreal units
+    #[argh(option, default = "Tolerance::default().position")]
+    position_tolerance: f32,
+
+    /// max angle between rotations in degrees
+    #[argh(option, default = "Tolerance::default().rotation")]
+    rotation_tolerance: f32,
+
+    /// max difference between scale components
+    #[argh(option, default = "Tolerance::default().scale")]
+    scale_tolerance: f32,
+}
+
+pub fn dedupe(args: &DedupeArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+    let tolerance = Tolerance {
+        position: args.position_tolerance,
+        rotation: args.rotation_tolerance,
+        scale: args.scale_tolerance,
+    };
+
+    let groups = dedupe::find_duplicates(&save, &tolerance);
+    for group in &groups {
+        println!("{} x{}", group.name, group.indices.len());
+        for &index in &group.indices {
+            let item = &save.items[index];
+            let p = &item.position;
+            let lost = match group.state_differs.contains(&index) {
+                true => ", its different state is lost when removed",
+                false => "",
+            };
+            println!(
+                "  [{index}] {} at ({}, {}, {}){lost}",
+                item.guid, p.x, p.y, p.z
+            );
+        }
+    }
+
+    let redundant: usize = groups.iter().map(|g| g.redundant().len()).sum();
+    let state_differs: usize = groups.iter().map(|g| g.state_differs.len()).sum();
+    println!(
+        "{} duplicate groups, {redundant} redundant items, {state_differs} with different state",
+        groups.len()
+    );
+
+    if let Some(output) = &args.output {
+        dedupe::remove_duplicates(&mut save, &groups);
+        write_save(&save, output, args.overwrite)?;
+    }
+
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Replace item classes using a json mapping file
+#[argh(subcommand, name = "replace-class")]
+pub struct ReplaceClassArgs {
+    /// save file to replace classes in
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// json file mapping old class names to their replacement
+    #[argh(option, short = 'm')]
+    mapping: PathBuf,
+
+    /// output location for the save file
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// only report what would be replaced
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+pub fn replace_class(args: &ReplaceClassArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+    let mapping: ClassMapping =
+        serde_json::from_reader(BufReader::new(File::open(&args.mapping)?))?;
+
+    let replaced = replace::replace_classes(&mut save, &mapping, args.dry_run);
+    for r in &replaced {
+        println!(
+            "[{}] {} {} -> {} (state: {:?})",
+            r.index, r.guid, r.from, r.to, r.state
+        );
+    }
+    println!("{} items replaced", replaced.len());
+
+    if args.dry_run {
+        return Ok(());
+    }
+    match &args.output {
+        Some(output) => write_save(&save, output, args.overwrite),
+        None => Err(anyhow::anyhow!(
+            "an output is required unless --dry-run is given"
+        )),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print the value of a property as json, in the `to-json --lossless` encoding
+#[argh(subcommand, name = "get")]
+pub struct GetArgs {
+    /// save file to read from
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// path to the value, e.g. `items[guid=...].tinyrick.properties.Color.R`
+    #[argh(option, short = 'p')]
+    path: PropertyPath,
+}
+
+pub fn get(input: &Path, path: &PropertyPath) -> anyhow::Result<()> {
+    let save = read_save(input)?;
+    let value = property_path::get(&save, path)?;
+    println!("{}", serde_json::to_string_pretty(&value)?);
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Change the value of a property
+#[argh(subcommand, name = "set")]
+pub struct SetArgs {
+    /// save file to change
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// output location for the save file
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// path to the value, e.g. `items[guid=...].tinyrick.properties.Color.R`
+    #[argh(option, short = 'p')]
+    path: PropertyPath,
+
+    /// new value as lossless json (see `to-json --lossless`), anything that isn't valid
+    /// json is used as a string
+    #[argh(option, short = 'v')]
+    value: String,
+}
+
+pub fn set(args: &SetArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+    let value = serde_json::from_str(&args.value)
+        .unwrap_or_else(|_| serde_json::Value::String(args.value.clone()));
+    property_path::set(&mut save, &args.path, value)?;
+    write_save(&save, &args.output, args.overwrite)
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Remove a property, struct field or array element
+#[argh(subcommand, name = "delete")]
+pub struct DeleteArgs {
+    /// save file to change
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// output location for the save file
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// path to the value to remove
+    #[argh(option, short = 'p')]
+    path: PropertyPath,
+}
+
+pub fn delete(args: &DeleteArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+    property_path::delete(&mut save, &args.path)?;
+    write_save(&save, &args.output, args.overwrite)
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the colors used by items, and optionally change them
+#[argh(subcommand, name = "recolor")]
+pub struct RecolorArgs {
+    /// save file to recolor
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// output location for the save file, without it the changes are only counted
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// json file with a list of `{ "from": color, "to": color }` entries
+    #[argh(option)]
+    palette: Option<PathBuf>,
+
+    /// how far a color can be from a palette entry and still match
+    #[argh(option, default = "0.01")]
+    tolerance: f32,
+
+    /// degrees to rotate the hue by
+    #[argh(option, default = "0.0")]
+    hue: f32,
+
+    /// factor to scale saturation by
+    #[argh(option, default = "1.0")]
+    saturation: f32,
+
+    /// factor to scale value by
+    #[argh(option, default = "1.0")]
+    value: f32,
+
+    /// color to tint towards, as `#rrggbb` or `r,g,b`
+    #[argh(option)]
+    tint: Option<Rgba>,
+
+    /// how strongly to tint, from 0 to 1, clamped to that range
+    #[argh(option, default = "0.5")]
+    tint_amount: f32,
+}
+
+pub fn recolor(args: &RecolorArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+
+    for usage in recolor::color_usage(&save)? {
+        println!("{:>6}x {:?} {}", usage.count, usage.kind, usage.color);
+    }
+
+    let recolor = if let Some(palette) = &args.palette {
+        let entries: Vec<PaletteEntry> =
+            serde_json::from_reader(BufReader::new(File::open(palette)?))?;
+        Recolor::Palette {
+            entries,
+            tolerance: args.tolerance,
+        }
+    } else if let Some(color) = args.tint {
+        Recolor::Tint {
+            color,
+            amount: args.tint_amount,
+        }
+    } else if args.hue != 0.0 || args.saturation != 1.0 || args.value != 1.0 {
+        Recolor::HsvShift {
+            hue: args.hue,
+            saturation: args.saturation,
+            value: args.value,
+        }
+    } else {
+        return Ok(());
+    };
+
+    let changed = recolor::recolor(&mut save, &recolor)?;
+    println!("{changed} colors changed");
+
+    match &args.output {
+        Some(output) => write_save(&save, output, args.overwrite),
+        None => {
+            println!("no output given with -o, nothing was written");
+            Ok(())
+        }
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Show which items changed between two save files
+#[argh(subcommand, name = "diff")]
+pub struct DiffArgs {
+    /// the original save file
+    #[argh(positional)]
+    old: PathBuf,
+
+    /// the changed save file
+    #[argh(positional)]
+    new: PathBuf,
+
+    /// print the differences as json
+    #[argh(switch)]
+    json: bool,
+
+    /// max distance between positions in unreal units
+    #[argh(option, default = "Tolerance::default().position")]
+    position_tolerance: f32,
+
+    /// max angle between rotations in degrees
+    #[argh(option, default = "Tolerance::default().rotation")]
+    rotation_tolerance: f32,
+
+    /// max difference between scale components
+    #[argh(option, default = "Tolerance::default().scale")]
+    scale_tolerance: f32,
+
+    /// max difference between numbers inside properties
+    #[argh(option, default = "DiffOptions::default().value")]
+    value_tolerance: f64,
+}
+
+pub fn diff(args: &DiffArgs) -> anyhow::Result<()> {
+    let old = read_save(&args.old)?;
+    let new = read_save(&args.new)?;
+    let options = DiffOptions {
+        transform: Tolerance {
+            position: args.position_tolerance,
+            rotation: args.rotation_tolerance,
+            scale: args.scale_tolerance,
+        },
+        value: args.value_tolerance,
+    };
+
+    let diff = diff::diff(&old, &new, &options)?;
+    match args.json {
+        true => println!("{}", serde_json::to_string_pretty(&diff)?),
+        false => print!("{diff}"),
+    }
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Three-way merge of two save files changed from a common ancestor
+#[argh(subcommand, name = "merge")]
+pub struct MergeArgs {
+    /// the common ancestor
+    #[argh(option)]
+    base: PathBuf,
+
+    /// our changed save file
+    #[argh(option)]
+    ours: PathBuf,
+
+    /// their changed save file
+    #[argh(option)]
+    theirs: PathBuf,
+
+    /// output location for the merged save file
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// write conflicts to this json file
+    #[argh(option)]
+    report: Option<PathBuf>,
+
+    /// write the merge as json with conflict markers instead of a save file
+    #[argh(switch)]
+    markers: bool,
+
+    /// max difference between numbers that still counts as unchanged
+    #[argh(option, default = "MergeOptions::default().tolerance")]
+    tolerance: f64,
+}
+
+pub fn merge(args: &MergeArgs) -> anyhow::Result<()> {
+    let base = read_save(&args.base)?;
+    let ours = read_save(&args.ours)?;
+    let theirs = read_save(&args.theirs)?;
+    let options = MergeOptions {
+        tolerance: args.tolerance,
+        markers: args.markers,
+    };
+
+    let result = merge::merge(&base, &ours, &theirs, &options)?;
+    let conflicts = result.conflicts.len();
+    for conflict in &result.conflicts {
+        println!("conflict: {}", conflict.path);
+    }
+    if let Some(report) = &args.report {
+        let writer = BufWriter::new(create_output(report, args.overwrite)?);
+        serde_json::to_writer_pretty(writer, &result.conflicts)?;
+    }
+
+    match args.markers {
+        true => {
+            let writer = BufWriter::new(create_output(&args.output, args.overwrite)?);
+            serde_json::to_writer_pretty(writer, &result.json)?;
+        }
+        false => write_save(&result.into_save()?, &args.output, args.overwrite)?,
+    }
+
+    match conflicts {
+        0 => Ok(()),
+        n => Err(anyhow::anyhow!("{n} conflicts, our side was kept")),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print a save as stable text lines, for use as a git textconv
+#[argh(subcommand, name = "textconv")]
+pub struct TextconvArgs {
+    /// save file to print
+    #[argh(positional)]
+    input: PathBuf,
+}
+
+pub fn print_textconv(input: &Path) -> anyhow::Result<()> {
+    let save = read_save(input)?;
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    textconv::render(&save, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Merge three save files in place, for use as a git merge driver (`%O %A %B`)
+#[argh(subcommand, name = "merge-driver")]
+pub struct MergeDriverArgs {
+    /// the common ancestor
+    #[argh(positional)]
+    base: PathBuf,
+
+    /// our version, overwritten with the merge result
+    #[argh(positional)]
+    ours: PathBuf,
+
+    /// their version
+    #[argh(positional)]
+    theirs: PathBuf,
+}
+
+pub fn merge_driver(args: &MergeDriverArgs) -> anyhow::Result<()> {
+    let base = read_save(&args.base)?;
+    let ours = read_save(&args.ours)?;
+    let theirs = read_save(&args.theirs)?;
+
+    let result = merge::merge(&base, &ours, &theirs, &MergeOptions::default())?;
+    let conflicts = result.conflicts.clone();
+    write_save(&result.into_save()?, &args.ours, true)?;
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    for conflict in &conflicts {
+        eprintln!("conflict: {}", conflict.path);
+    }
+    Err(anyhow::anyhow!(
+        "{} conflicts, our side was kept",
+        conflicts.len()
+    ))
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Split a save file into a directory with one json file per item
+#[argh(subcommand, name = "explode")]
+pub struct ExplodeArgs {
+    /// save file to split
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// directory to write the files to
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+
+    /// write into the directory even if it already exists?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+}
+
+pub fn explode(input: &Path, output: &Path, overwrite: bool) -> anyhow::Result<()> {
+    if output.exists() && !overwrite {
+        return Err(anyhow::anyhow!("{} already exists", output.display()));
+    }
+    let save = read_save(input)?;
+    explode::explode(&save, output)
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Rebuild a save file from a directory written by explode
+#[argh(subcommand, name = "implode")]
+pub struct ImplodeArgs {
+    /// directory written by explode
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// output location for the save file
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+}
+
+pub fn implode(input: &Path, output: &Path, overwrite: bool) -> anyhow::Result<()> {
+    let save = explode::implode(input)?;
+    write_save(&save, output, overwrite)
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Summarize the contents of a save file
+#[argh(subcommand, name = "stats")]
+pub struct StatsArgs {
+    /// save file to summarize
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// print the summary as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn print_stats(input: &Path, json: bool) -> anyhow::Result<()> {
+    let save = read_save(input)?;
+    let stats = stats::stats(&save);
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&stats)?),
+        false => print!("{stats}"),
+    }
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the items a save needs, and what is missing from an inventory
+#[argh(subcommand, name = "bom")]
+pub struct BomArgs {
+    /// save file to list the items of
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// player inventory save to compare against
+    #[argh(option)]
+    inventory: Option<PathBuf>,
+
+    /// property naming the item of an inventory entry, can be repeated
+    #[argh(option)]
+    name_field: Vec<String>,
+
+    /// property holding the count of an inventory entry, can be repeated
+    #[argh(option)]
+    count_field: Vec<String>,
+
+    /// print the bill of materials as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn bom(args: &BomArgs) -> anyhow::Result<()> {
+    let save = read_save(&args.input)?;
+    let required = bom::required_items(&save);
+
+    let owned = match &args.inventory {
+        Some(inventory) => {
+            let mut fields = InventoryFields::default();
+            if !args.name_field.is_empty() {
+                fields.name = args.name_field.clone();
+            }
+            if !args.count_field.is_empty() {
+                fields.count = args.count_field.clone();
+            }
+            let mut reader = BufReader::new(File::open(inventory)?);
+            bom::read_inventory(&mut reader, &fields)?
+        }
+        None => Default::default(),
+    };
+
+    let lines = bom::bill_of_materials(&required, &owned);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&lines)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:>6} {:>6} {:>7} {:>7} name",
+        "needed", "owned", "missing", "surplus"
+    );
+    for line in &lines {
+        println!(
+            "{:>6} {:>6} {:>7} {:>7} {}",
+            line.needed,
+            line.owned,
+            line.missing(),
+            line.surplus(),
+            line.name
+        );
+    }
+    let missing: usize = lines.iter().map(|line| line.missing()).sum();
+    println!("{missing} items missing");
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the workshop items a save depends on
+#[argh(subcommand, name = "workshop")]
+pub struct WorkshopArgs {
+    /// save file to list the workshop items of
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// steam `steamapps/workshop` directory to check for installed items
+    #[argh(option)]
+    workshop_dir: Option<PathBuf>,
+
+    /// steam app id the workshop items belong to
+    #[argh(option, default = "TOWER_UNITE_APP_ID")]
+    app_id: u32,
+
+    /// print the dependencies as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn list_workshop(args: &WorkshopArgs) -> anyhow::Result<()> {
+    let save = read_save(&args.input)?;
+    let dependencies = workshop::dependencies(&save);
+    let installed = match &args.workshop_dir {
+        Some(dir) => Some(InstalledWorkshop::read(dir, args.app_id)?),
+        None => None,
+    };
+
+    if args.json {
+        let dependencies: Vec<_> = dependencies
+            .iter()
+            .map(|dependency| -> anyhow::Result<serde_json::Value> {
+                let mut value = serde_json::to_value(dependency)?;
+                if let Some(installed) = &installed {
+                    value["installed"] = installed.is_installed(dependency.id).into();
+                }
+                Ok(value)
+            })
+            .collect::<anyhow::Result<_>>()?;
+        println!("{}", serde_json::to_string_pretty(&dependencies)?);
+        return Ok(());
+    }
+
+    for dependency in &dependencies {
+        let status = match &installed {
+            Some(installed) if !installed.in_manifest.contains(&dependency.id) => {
+                " (not installed)"
+            }
+            Some(installed) if !installed.on_disk.contains(&dependency.id) => " (missing files)",
+            _ => "",
+        };
+        println!("{:>6}x {}{status}", dependency.uses, dependency.id.url());
+        for class in &dependency.classes {
+            println!("          {class}");
+        }
+    }
+    if let Some(installed) = &installed {
+        let missing = dependencies
+            .iter()
+            .filter(|dependency| !installed.is_installed(dependency.id))
+            .count();
+        println!(
+            "{missing} of {} workshop items not installed",
+            dependencies.len()
+        );
+    }
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Show which items, classes and properties take up the most bytes
+#[argh(subcommand, name = "size")]
+pub struct SizeArgs {
+    /// save file to measure
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// how many entries to list per section
+    #[argh(option, default = "10")]
+    top: usize,
+
+    /// print the report as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn print_size(args: &SizeArgs) -> anyhow::Result<()> {
+    let save = read_save(&args.input)?;
+    let report = size::size_report(&save)?.top(args.top);
+    match args.json {
+        true => println!("{}", serde_json::to_string_pretty(&report)?),
+        false => print!("{report}"),
+    }
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Remove tinyrick properties that only repeat the defaults of their class
+#[argh(subcommand, name = "compact")]
+pub struct CompactArgs {
+    /// save file to compact
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// where to write the compacted save
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// json file of default properties per class, learned from the save when missing
+    #[argh(option)]
+    defaults: Option<PathBuf>,
+
+    /// write the defaults that were used to a json file
+    #[argh(option)]
+    save_defaults: Option<PathBuf>,
+
+    /// classes with fewer items with state aren't learned from
+    #[argh(option, default = "LearnOptions::default().min_items")]
+    min_items: usize,
+
+    /// share of the items of a class that must agree on a value to learn it
+    #[argh(option, default = "LearnOptions::default().min_share")]
+    min_share: f64,
+
+    /// print the report as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn compact(args: &CompactArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+    let defaults: ClassDefaults = match &args.defaults {
+        Some(path) => serde_json::from_reader(BufReader::new(File::open(path)?))?,
+        None => compact::learn_defaults(
+            &save,
+            &LearnOptions {
+                min_items: args.min_items,
+                min_share: args.min_share,
+            },
+        )?,
+    };
+    if let Some(path) = &args.save_defaults {
+        let writer = BufWriter::new(create_output(path, args.overwrite)?);
+        serde_json::to_writer_pretty(writer, &defaults)?;
+    }
+
+    let report = compact::compact(&mut save, &defaults)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for compacted in &report.compacted {
+            let dropped = match compacted.dropped_state {
+                true => ", state dropped",
+                false => "",
+            };
+            println!(
+                "[{}] {} {}: -{} bytes, removed {}{dropped}",
+                compacted.index,
+                compacted.name,
+                compacted.guid,
+                compacted.bytes_saved,
+                compacted.removed.join(", ")
+            );
+        }
+        for kept in &report.kept {
+            println!("[{}] {} kept: {}", kept.index, kept.guid, kept.reason);
+        }
+        println!(
+            "{} items compacted, {} kept, {} bytes saved",
+            report.compacted.len(),
+            report.kept.len(),
+            report.bytes_saved
+        );
+    }
+
+    if let Some(output) = &args.output {
+        write_save(&save, output, args.overwrite)?;
+    }
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Check a save for duplicate guids, broken transforms and more
+#[argh(subcommand, name = "lint")]
+pub struct LintArgs {
+    /// save file to check
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// toml file enabling and configuring rules, see `lint` for the format
+    #[argh(option, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// fail when a finding is at least this severe: info, warning or error
+    #[argh(option, default = "Severity::Error")]
+    fail_on: Severity,
+
+    /// print the findings as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn lint(args: &LintArgs) -> anyhow::Result<()> {
+    let save = read_save(&args.input)?;
+    let config = match &args.config {
+        Some(path) => LintConfig::from_toml(&std::fs::read_to_string(path)?)?,
+        None => LintConfig::default(),
+    };
+
+    let findings = lint::lint(&save, &config)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else {
+        for finding in &findings {
+            println!("{finding}");
+        }
+        println!("{} findings", findings.len());
+    }
+
+    let failing = findings
+        .iter()
+        .filter(|finding| finding.severity >= args.fail_on)
+        .count();
+    match failing {
+        0 => Ok(()),
+        n => Err(anyhow::anyhow!("{n} findings at {} or above", args.fail_on)),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Normalize rotations, repair NaN and infinite values and clamp scales
+#[argh(subcommand, name = "sanitize")]
+pub struct SanitizeArgs {
+    /// save file to sanitize
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// where to write the sanitized save
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// what to do with NaN or infinite values: reset or drop the item
+    #[argh(option, default = "NonFinite::Reset")]
+    non_finite: NonFinite,
+
+    /// smallest allowed size of a scale component, mirrored scales stay mirrored
+    #[argh(option, default = "SanitizeOptions::default().min_scale")]
+    min_scale: f32,
+
+    /// largest allowed size of a scale component
+    #[argh(option, default = "SanitizeOptions::default().max_scale")]
+    max_scale: f32,
+
+    /// print the changes as json
+    #[argh(switch)]
+    json: bool,
+}
+
+pub fn sanitize(args: &SanitizeArgs) -> anyhow::Result<()> {
+    let mut save = read_save(&args.input)?;
+    let options = SanitizeOptions {
+        non_finite: args.non_finite,
+        min_scale: args.min_scale,
+        max_scale: args.max_scale,
+        ..Default::default()
+    };
+
+    let changes = sanitize::sanitize(&mut save, &options);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else {
+        for change in &changes {
+            println!("{change}");
+        }
+        println!("{} changes", changes.len());
+    }
+
+    if let Some(output) = &args.output {
+        write_save(&save, output, args.overwrite)?;
+    }
+    Ok(())
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Convert between a save and json, yaml, ron, toml, xml, messagepack or cbor
+#[argh(subcommand, name = "convert")]
+pub struct ConvertArgs {
+    /// file to convert
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+
+    /// output location
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+
+    /// format of the input, guessed from its extension otherwise
+    #[argh(option)]
+    from: Option<Format>,
+
+    /// format of the output, guessed from its extension otherwise
+    #[argh(option)]
+    to: Option<Format>,
+
+    /// keep every float bit for bit, see `to-json --lossless`
+    #[argh(switch)]
+    lossless: bool,
+
+    /// format version written when the input has no header
+    #[argh(option, default = "Defaults::default().format_version")]
+    format_version: u32,
+
+    /// unreal version written when the input has no header
+    #[argh(option, default = "Defaults::default().unreal_version")]
+    unreal_version: u32,
+}
+
+pub fn convert(args: &ConvertArgs) -> anyhow::Result<()> {
+    let from = args.from.unwrap_or_else(|| Format::from_path(&args.input));
+    let to = args.to.unwrap_or_else(|| Format::from_path(&args.output));
+
+    // saves and the binary formats are read as the save itself, with every float kept
+    let save = match from {
+        Format::Save => read_save(&args.input)?,
+        from if from.is_binary() => convert::parse_save(from, &std::fs::read(&args.input)?)?,
+        from => {
+            let mut json = convert::parse(from, &std::fs::read(&args.input)?)?;
+            if to != Format::Save && !to.is_binary() {
+                let output = convert::print(to, &json)?;
+                create_output(&args.output, args.overwrite)?.write_all(&output)?;
+                return Ok(());
+            }
+
+            // partial items, as `to-save` takes them
+            let defaults = Defaults {
+                format_version: args.format_version,
+                unreal_version: args.unreal_version,
+            };
+            defaults::fill_defaults(&mut json, &defaults);
+            validate::from_value(json, args.lossless)?
+        }
+    };
+
+    match to {
+        Format::Save => write_save(&save, &args.output, args.overwrite),
+        to => {
+            let output = match to.is_binary() {
+                true => convert::print_save(to, &save)?,
+                false => {
+                    let json = match args.lossless {
+                        true => lossless::to_value(&save)?,
+                        false => serde_json::to_value(&save)?,
+                    };
+                    convert::print(to, &json)?
+                }
+            };
+            create_output(&args.output, args.overwrite)?.write_all(&output)?;
+            Ok(())
+        }
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print a JSON Schema for the json written by to-json
+#[argh(subcommand, name = "schema")]
+pub struct SchemaArgs {
+    /// where to write the schema, printed otherwise
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// overwrite the output file?
+    #[argh(switch, short = '!')]
+    overwrite: bool,
+}
+
+pub fn print_schema(args: &SchemaArgs) -> anyhow::Result<()> {
+    let schema = json_schema::suitebro_schema();
+    match &args.output {
+        Some(output) => {
+            let writer = BufWriter::new(create_output(output, args.overwrite)?);
+            serde_json::to_writer_pretty(writer, &schema)?;
+        }
+        None => println!("{}", serde_json::to_string_pretty(&schema)?),
+    }
     Ok(())
 }
 
@@ -108,6 +1240,27 @@ enum SubCommand {
     ToJSON(ToJSONArgs),
     ToSave(ToSaveArgs),
     Check(CheckArgs),
+    Dedupe(DedupeArgs),
+    ReplaceClass(ReplaceClassArgs),
+    Get(GetArgs),
+    Set(SetArgs),
+    Delete(DeleteArgs),
+    Recolor(RecolorArgs),
+    Diff(DiffArgs),
+    Merge(MergeArgs),
+    Textconv(TextconvArgs),
+    MergeDriver(MergeDriverArgs),
+    Explode(ExplodeArgs),
+    Implode(ImplodeArgs),
+    Stats(StatsArgs),
+    Bom(BomArgs),
+    Workshop(WorkshopArgs),
+    Size(SizeArgs),
+    Compact(CompactArgs),
+    Lint(LintArgs),
+    Sanitize(SanitizeArgs),
+    Convert(ConvertArgs),
+    Schema(SchemaArgs),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -121,8 +1274,147 @@ pub fn main() -> anyhow::Result<()> {
     let args: Args = argh::from_env();
 
     match args.subcommand {
-        SubCommand::ToJSON(args) => to_json(&args.input, &args.output, args.overwrite),
-        SubCommand::ToSave(args) => from_json(&args.input, &args.output, args.overwrite),
-        SubCommand::Check(args) => check(&args.input),
+        SubCommand::ToJSON(args) => to_json(&args),
+        SubCommand::ToSave(args) => from_json(&args),
+        SubCommand::Check(args) => check(&args),
+        SubCommand::Dedupe(args) => dedupe(&args),
+        SubCommand::ReplaceClass(args) => replace_class(&args),
+        SubCommand::Get(args) => get(&args.input, &args.path),
+        SubCommand::Set(args) => set(&args),
+        SubCommand::Delete(args) => delete(&args),
+        SubCommand::Recolor(args) => recolor(&args),
+        SubCommand::Diff(args) => diff(&args),
+        SubCommand::Merge(args) => merge(&args),
+        SubCommand::Textconv(args) => print_textconv(&args.input),
+        SubCommand::MergeDriver(args) => merge_driver(&args),
+        SubCommand::Explode(args) => explode(&args.input, &args.output, args.overwrite),
+        SubCommand::Implode(args) => implode(&args.input, &args.output, args.overwrite),
+        SubCommand::Stats(args) => print_stats(&args.input, args.json),
+        SubCommand::Bom(args) => bom(&args),
+        SubCommand::Workshop(args) => list_workshop(&args),
+        SubCommand::Size(args) => print_size(&args),
+        SubCommand::Compact(args) => compact(&args),
+        SubCommand::Lint(args) => lint(&args),
+        SubCommand::Sanitize(args) => sanitize(&args),
+        SubCommand::Convert(args) => convert(&args),
+        SubCommand::Schema(args) => print_schema(&args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes OneItem, changed by `edit`, to `dir/name`.
+    fn write_one_item(dir: &Path, name: &str, edit: impl FnOnce(&mut SuiteBro)) -> PathBuf {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/OneItem");
+        let mut save = read_save(&fixture).unwrap();
+        edit(&mut save);
+        let path = dir.join(name);
+        write_save(&save, &path, true).unwrap();
+        path
+    }
+
+    fn merge_in(
+        dir: &Path,
+        ours: impl FnOnce(&mut SuiteBro),
+        theirs: impl FnOnce(&mut SuiteBro),
+    ) -> (anyhow::Result<()>, SuiteBro) {
+        std::fs::create_dir_all(dir).unwrap();
+        let args = MergeDriverArgs {
+            base: write_one_item(dir, "base", |_| {}),
+            ours: write_one_item(dir, "ours", ours),
+            theirs: write_one_item(dir, "theirs", theirs),
+        };
+        let result = merge_driver(&args);
+        let merged = read_save(&args.ours).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        (result, merged)
+    }
+
+    #[test]
+    fn test_merge_driver_clean() {
+        let dir = std::env::temp_dir().join(format!("suitebro-merge-{}", std::process::id()));
+        let (result, merged) = merge_in(
+            &dir,
+            |save| save.items[0].position.x += 10.0,
+            |save| save.items[0].name = "Renamed".to_string(),
+        );
+        assert!(result.is_ok());
+
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/OneItem");
+        let mut expected = read_save(&fixture).unwrap();
+        expected.items[0].position.x += 10.0;
+        expected.items[0].name = "Renamed".to_string();
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn test_merge_driver_conflict() {
+        let dir = std::env::temp_dir().join(format!("suitebro-conflict-{}", std::process::id()));
+        let (result, merged) = merge_in(
+            &dir,
+            |save| save.items[0].position.x = 1.0,
+            |save| {
+                save.items[0].position.x = 2.0;
+                save.items[0].name = "Renamed".to_string();
+            },
+        );
+        assert!(result.unwrap_err().to_string().starts_with("1 conflicts"));
+        // %A still holds a valid save, with our side of the conflict
+        assert_eq!(merged.items[0].position.x, 1.0);
+        assert_eq!(merged.items[0].name, "Renamed");
+    }
+
+    #[test]
+    fn test_check_flags() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/OneItem");
+        let check_with = |roundtrip, via_json, lossless| {
+            check(&CheckArgs {
+                input: fixture.clone(),
+                roundtrip,
+                via_json,
+                lossless,
+            })
+        };
+        assert!(check_with(false, true, false).is_err());
+        assert!(check_with(false, false, true).is_err());
+        assert!(check_with(true, false, true).is_err());
+        assert!(check_with(false, false, false).is_ok());
+    }
+
+    #[test]
+    fn test_convert_partial() {
+        let dir = std::env::temp_dir().join(format!("suitebro-convert-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("partial.yaml");
+        let yaml = "items:\n  - name: Chair\n    position: { x: 1.0, y: 2.0, z: 3.0 }\n";
+        std::fs::write(&input, yaml).unwrap();
+
+        let convert_to = |to, output: &str| {
+            convert(&ConvertArgs {
+                input: input.clone(),
+                output: dir.join(output),
+                overwrite: true,
+                from: None,
+                to: Some(to),
+                lossless: false,
+                format_version: 1,
+                unreal_version: 522,
+            })
+        };
+        convert_to(Format::Save, "Partial").unwrap();
+        convert_to(Format::MessagePack, "partial.msgpack").unwrap();
+
+        let save = read_save(&dir.join("Partial")).unwrap();
+        let binary = std::fs::read(dir.join("partial.msgpack")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(save.header.unreal_version, 522);
+        assert_eq!(save.items[0].scale.x, 1.0);
+        assert_eq!(save.items[0].rotation.w, 1.0);
+        assert!(!save.items[0].guid.is_nil());
+        let binary = convert::parse_save(Format::MessagePack, &binary).unwrap();
+        assert_eq!(binary.items[0].position.z, 3.0);
     }
 }
@@ -0,0 +1,191 @@
+//! Bill of materials: the items a save needs compared to what a player owns.
+//!
+//! Inventory saves are plain GVAS files. Their layout isn't fixed here, instead every
+//! struct in the save that has a name field is counted as an inventory entry, using its
+//! count field when it has one and 1 otherwise.
+
+use std::io::{Read, Seek};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::property_path::unwrap_value;
+use crate::suitebro::SuiteBro;
+
+/// Field names, matched case insensitively, that identify inventory entries.
+///
+/// Only properties match, never the plain strings uesave keeps next to them such as the
+/// `name` of an array's struct type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryFields {
+    pub name: Vec<String>,
+    pub count: Vec<String>,
+}
+
+impl Default for InventoryFields {
+    fn default() -> Self {
+        InventoryFields {
+            name: ["ItemName", "ClassName", "Item", "Name"]
+                .map(String::from)
+                .to_vec(),
+            count: ["Count", "Amount", "Quantity", "StackCount"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// The bare class name, so `/Game/Items/Chair.Chair_C` and `Chair` compare equal.
+pub fn class_key(name: &str) -> &str {
+    let name = name.rsplit('/').next().unwrap_or(name);
+    let name = name.rsplit('.').next().unwrap_or(name);
+    name.strip_suffix("_C").unwrap_or(name)
+}
+
+/// Item count per class name.
+pub fn required_items(save: &SuiteBro) -> IndexMap<String, usize> {
+    let mut required = IndexMap::new();
+    for item in &save.items {
+        *required
+            .entry(class_key(&item.name).to_string())
+            .or_insert(0) += 1;
+    }
+    required
+}
+
+fn field<'v>(object: &'v serde_json::Map<String, Value>, names: &[String]) -> Option<&'v Value> {
+    names.iter().find_map(|name| {
+        object
+            .iter()
+            .find(|(key, value)| key.eq_ignore_ascii_case(name) && value.is_object())
+            .map(|(_, value)| unwrap_value(value))
+    })
+}
+
+fn count_inventory(value: &Value, fields: &InventoryFields, counts: &mut IndexMap<String, usize>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(name)) = field(object, &fields.name) {
+                let count = field(object, &fields.count)
+                    .and_then(Value::as_u64)
+                    .unwrap_or(1);
+                *counts.entry(class_key(name).to_string()).or_insert(0) += count as usize;
+                return;
+            }
+            for child in object.values() {
+                count_inventory(child, fields, counts);
+            }
+        }
+        Value::Array(array) => {
+            for child in array {
+                count_inventory(child, fields, counts);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Item count per class name in the serde representation of an inventory save.
+pub fn inventory_counts(inventory: &Value, fields: &InventoryFields) -> IndexMap<String, usize> {
+    let mut counts = IndexMap::new();
+    count_inventory(inventory, fields, &mut counts);
+    counts
+}
+
+/// Reads a GVAS inventory save and counts the items in it.
+pub fn read_inventory<R: Read + Seek>(
+    reader: &mut R,
+    fields: &InventoryFields,
+) -> anyhow::Result<IndexMap<String, usize>> {
+    let inventory = uesave::Save::read(reader)?;
+    Ok(inventory_counts(&serde_json::to_value(&inventory)?, fields))
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BomLine {
+    pub name: String,
+    pub needed: usize,
+    pub owned: usize,
+}
+
+impl BomLine {
+    pub fn missing(&self) -> usize {
+        self.needed.saturating_sub(self.owned)
+    }
+
+    pub fn surplus(&self) -> usize {
+        self.owned.saturating_sub(self.needed)
+    }
+}
+
+/// One line per class that is either needed or owned, needed classes first.
+pub fn bill_of_materials(
+    required: &IndexMap<String, usize>,
+    owned: &IndexMap<String, usize>,
+) -> Vec<BomLine> {
+    let needed = required.iter().map(|(name, &needed)| BomLine {
+        name: name.clone(),
+        needed,
+        owned: owned.get(name).copied().unwrap_or(0),
+    });
+    let extra = owned
+        .iter()
+        .filter(|(name, _)| !required.contains_key(*name))
+        .map(|(name, &owned)| BomLine {
+            name: name.clone(),
+            needed: 0,
+            owned,
+        });
+    needed.chain(extra).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_class_key() {
+        assert_eq!(class_key("/Game/Items/Chair.Chair_C"), "Chair");
+        assert_eq!(class_key("Chair"), "Chair");
+    }
+
+    #[test]
+    fn test_inventory_counts() {
+        let inventory = json!({
+            "Inventory": { "Array": { "value": { "Struct": { "value": [
+                { "Struct": {
+                    "ItemName": { "Str": { "id": null, "value": "/Game/Chair.Chair_C" } },
+                    "Count": { "Int": { "id": null, "value": 3 } },
+                } },
+                { "Struct": {
+                    "ItemName": { "Str": { "id": null, "value": "Lamp" } },
+                } },
+            ] } } } }
+        });
+        let owned = inventory_counts(&inventory, &InventoryFields::default());
+        assert_eq!(owned.get("Chair"), Some(&3));
+        assert_eq!(owned.get("Lamp"), Some(&1));
+
+        let required = IndexMap::from([("Chair".to_string(), 5), ("Table".to_string(), 1)]);
+        let bom = bill_of_materials(&required, &owned);
+        assert_eq!(bom[0].missing(), 2);
+        assert_eq!(bom[1].missing(), 1);
+        assert_eq!(bom[2].name, "Lamp");
+        assert_eq!(bom[2].surplus(), 1);
+    }
+
+    #[test]
+    fn test_read_inventory() -> anyhow::Result<()> {
+        // an `Items` array of `InventoryEntry` structs: Chair_C x3, Lamp_C, Chair x2
+        let input = include_bytes!("../assets/gvas/Inventory.sav");
+        let owned = read_inventory(&mut Cursor::new(&input[..]), &InventoryFields::default())?;
+        assert_eq!(
+            owned,
+            IndexMap::from([("Chair".to_string(), 5), ("Lamp".to_string(), 1)])
+        );
+        Ok(())
+    }
+}
@@ -117,3 +117,7 @@ impl_static_size!(std::num::NonZeroU16, 2);
 impl_static_size!(std::num::NonZeroU32, 4);
 impl_static_size!(std::num::NonZeroU64, 8);
 impl_static_size!(std::num::NonZeroU128, 16);
+
+impl_static_size_struct!(uuid::Uuid { u128 });
+impl_static_size_struct!(uesave::Vector { f32, f32, f32 });
+impl_static_size_struct!(uesave::Quat { f32, f32, f32, f32 });
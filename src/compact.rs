@@ -0,0 +1,346 @@
+//! Removing tinyrick properties that only repeat the defaults of their class.
+//!
+//! Defaults are either given per class or learned from the save itself. A learned
+//! default is a value every item of a class agrees on (or a `min_share` of them), which
+//! assumes the most common value is the one the game falls back to. Nothing here can
+//! check that assumption, a wrong default silently changes the items it is removed from.
+//!
+//! Each item is only rewritten when the result is smaller and decodes to the same
+//! compacted item, which guards against writing something unreadable, not against a
+//! wrong default. Otherwise the original is kept.
+
+use std::io::Cursor;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use uesave::{Readable, Writable};
+
+use crate::lossless;
+use crate::size::{measure, write_bytes};
+use crate::suitebro::get_tower_types;
+use crate::suitebro::item::Item;
+use crate::suitebro::SuiteBro;
+
+/// Default property values, in their lossless json form, per class name.
+pub type ClassDefaults = IndexMap<String, Map<String, Value>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LearnOptions {
+    /// classes with fewer items with state aren't learned from
+    pub min_items: usize,
+    /// share of the items of a class that must hold a value for it to become the default
+    pub min_share: f64,
+}
+
+impl Default for LearnOptions {
+    fn default() -> Self {
+        LearnOptions {
+            min_items: 3,
+            min_share: 1.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ClassValues {
+    items: usize,
+    /// property name -> json text of a value -> (items holding it, value)
+    properties: IndexMap<String, IndexMap<String, (usize, Value)>>,
+}
+
+/// Learns the default properties of every class from the items in the save.
+pub fn learn_defaults(save: &SuiteBro, options: &LearnOptions) -> anyhow::Result<ClassDefaults> {
+    let mut classes: IndexMap<&str, ClassValues> = IndexMap::new();
+    for item in &save.items {
+        let Some(tinyrick) = &item.tinyrick else {
+            continue;
+        };
+        let class = classes.entry(&item.name).or_default();
+        class.items += 1;
+        for (name, property) in &tinyrick.properties {
+            let value = lossless::to_value(property)?;
+            let values = class.properties.entry(name.clone()).or_default();
+            values.entry(value.to_string()).or_insert((0, value)).0 += 1;
+        }
+    }
+
+    let mut defaults = ClassDefaults::new();
+    for (name, class) in classes {
+        if class.items < options.min_items {
+            continue;
+        }
+        let mut properties = Map::new();
+        for (property, values) in class.properties {
+            let most_common = values.into_values().max_by_key(|(count, _)| *count);
+            if let Some((count, value)) = most_common {
+                if count as f64 / class.items as f64 >= options.min_share {
+                    properties.insert(property, value);
+                }
+            }
+        }
+        if !properties.is_empty() {
+            defaults.insert(name.to_string(), properties);
+        }
+    }
+    Ok(defaults)
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Compacted {
+    pub index: usize,
+    pub guid: uuid::Uuid,
+    pub name: String,
+    /// properties removed from the tinyrick
+    pub removed: Vec<String>,
+    /// the tinyrick was left empty and removed as a whole
+    pub dropped_state: bool,
+    pub bytes_saved: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Kept {
+    pub index: usize,
+    pub guid: uuid::Uuid,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct CompactReport {
+    pub compacted: Vec<Compacted>,
+    /// items with default values that were left as they were
+    pub kept: Vec<Kept>,
+    pub bytes_saved: usize,
+}
+
+/// Whether the item reads back from its own bytes unchanged, says nothing about defaults.
+fn round_trips(item: &Item) -> bool {
+    let Ok(bytes) = write_bytes(|ctx| item.write(ctx)) else {
+        return false;
+    };
+    let read = uesave::Context::run_with_types(
+        &get_tower_types(),
+        &mut Cursor::new(&bytes[..]),
+        Item::read,
+    );
+    matches!(read, Ok(read) if read == *item)
+}
+
+enum Outcome {
+    Unchanged,
+    Compacted {
+        removed: Vec<String>,
+        dropped_state: bool,
+        bytes_saved: usize,
+    },
+    Kept(&'static str),
+}
+
+fn compact_item(item: &mut Item, defaults: &Map<String, Value>) -> anyhow::Result<Outcome> {
+    let Some(tinyrick) = &item.tinyrick else {
+        return Ok(Outcome::Unchanged);
+    };
+    let mut removed = vec![];
+    for (name, property) in &tinyrick.properties {
+        if defaults.get(name) == Some(&lossless::to_value(property)?) {
+            removed.push(name.clone());
+        }
+    }
+    if removed.is_empty() {
+        return Ok(Outcome::Unchanged);
+    }
+    let before = measure(|ctx| item.write(ctx))?;
+
+    let tinyrick = item.tinyrick.as_mut().unwrap();
+    let mut order = vec![];
+    let mut stripped = vec![];
+    for (name, property) in std::mem::take(&mut tinyrick.properties) {
+        order.push(name.clone());
+        match removed.contains(&name) {
+            true => stripped.push((name, property)),
+            false => {
+                tinyrick.properties.insert(name, property);
+            }
+        }
+    }
+    let dropped = match tinyrick.properties.is_empty() && tinyrick.actors.is_empty() {
+        true => item.tinyrick.take(),
+        false => None,
+    };
+
+    let reason = match measure(|ctx| item.write(ctx)) {
+        Ok(after) if after < before && round_trips(item) => {
+            return Ok(Outcome::Compacted {
+                removed,
+                dropped_state: dropped.is_some(),
+                bytes_saved: before - after,
+            });
+        }
+        Ok(after) if after >= before => "compacting doesn't make it smaller",
+        Ok(_) => "the compacted item doesn't read back the same",
+        Err(_) => "the compacted item can't be written",
+    };
+
+    // unsure, put everything back the way it was
+    if let Some(dropped) = dropped {
+        item.tinyrick = Some(dropped);
+    }
+    let tinyrick = item.tinyrick.as_mut().unwrap();
+    let mut properties: IndexMap<String, uesave::Property> =
+        std::mem::take(&mut tinyrick.properties)
+            .into_iter()
+            .chain(stripped)
+            .collect();
+    for name in order {
+        if let Some(property) = properties.swap_remove(&name) {
+            tinyrick.properties.insert(name, property);
+        }
+    }
+    Ok(Outcome::Kept(reason))
+}
+
+/// Removes default valued properties from every item of a class in `defaults`.
+pub fn compact(save: &mut SuiteBro, defaults: &ClassDefaults) -> anyhow::Result<CompactReport> {
+    let mut report = CompactReport::default();
+    for (index, item) in save.items.iter_mut().enumerate() {
+        let Some(defaults) = defaults.get(&item.name) else {
+            continue;
+        };
+        match compact_item(item, defaults)? {
+            Outcome::Unchanged => {}
+            Outcome::Compacted {
+                removed,
+                dropped_state,
+                bytes_saved,
+            } => {
+                report.bytes_saved += bytes_saved;
+                report.compacted.push(Compacted {
+                    index,
+                    guid: item.guid,
+                    name: item.name.clone(),
+                    removed,
+                    dropped_state,
+                    bytes_saved,
+                });
+            }
+            Outcome::Kept(reason) => report.kept.push(Kept {
+                index,
+                guid: item.guid,
+                reason: reason.to_string(),
+            }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::one_item;
+    use serde_json::json;
+
+    #[test]
+    fn test_learn_defaults() -> anyhow::Result<()> {
+        let save = one_item();
+        assert!(learn_defaults(&save, &LearnOptions::default())?.is_empty());
+
+        let options = LearnOptions {
+            min_items: 1,
+            ..Default::default()
+        };
+        let defaults = learn_defaults(&save, &options)?;
+        let item = &save.items[0];
+        let learned = &defaults[&item.name];
+        let properties = &item.tinyrick.as_ref().unwrap().properties;
+        assert_eq!(learned.len(), properties.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact() -> anyhow::Result<()> {
+        // three items of a class that agree on everything and one of its own
+        let mut save = one_item();
+        for guid in 1..=3 {
+            let mut item = one_item().items.remove(0);
+            item.guid = uuid::Uuid::from_u128(guid);
+            if guid == 3 {
+                item.name = "Unique".to_string();
+            }
+            save.items.push(item);
+        }
+        let unique = serde_json::to_value(&save.items[3])?;
+        let properties: Vec<String> = save.items[0]
+            .tinyrick
+            .as_ref()
+            .unwrap()
+            .properties
+            .keys()
+            .cloned()
+            .collect();
+        assert!(!properties.is_empty());
+
+        let defaults = learn_defaults(&save, &LearnOptions::default())?;
+        assert_eq!(defaults.len(), 1);
+        let report = compact(&mut save, &defaults)?;
+
+        let compacted: Vec<_> = report.compacted.iter().map(|c| c.index).collect();
+        assert_eq!(compacted, [0, 1, 2]);
+        assert!(report.kept.is_empty());
+        for compacted in &report.compacted {
+            assert_eq!(compacted.removed, properties);
+            assert!(compacted.bytes_saved > 0);
+            let state = save.items[compacted.index].tinyrick.as_ref();
+            assert_eq!(compacted.dropped_state, state.is_none());
+        }
+        let saved: usize = report.compacted.iter().map(|c| c.bytes_saved).sum();
+        assert_eq!(report.bytes_saved, saved);
+        // too few of its class to learn from
+        assert_eq!(serde_json::to_value(&save.items[3])?, unique);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_no_defaults() -> anyhow::Result<()> {
+        let mut save = one_item();
+        let before = serde_json::to_value(&save)?;
+        let mut defaults = learn_defaults(
+            &save,
+            &LearnOptions {
+                min_items: 1,
+                ..Default::default()
+            },
+        )?;
+        for properties in defaults.values_mut() {
+            for value in properties.values_mut() {
+                *value = Value::Null;
+            }
+        }
+        let report = compact(&mut save, &defaults)?;
+        assert!(report.compacted.is_empty() && report.kept.is_empty());
+        assert_eq!(serde_json::to_value(&save)?, before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_non_finite() -> anyhow::Result<()> {
+        let mut save = one_item();
+        let brightness = json!({ "Float": { "id": null, "value": "#f32:7f800000" } });
+        let item = &mut save.items[0];
+        let tinyrick = item.tinyrick.as_mut().unwrap();
+        tinyrick
+            .properties
+            .insert("Brightness".into(), lossless::from_value(brightness)?);
+
+        // infinity is null in plain json, it isn't the default
+        let default = json!({ "Float": { "id": null, "value": null } });
+        let defaults = ClassDefaults::from([(
+            item.name.clone(),
+            Map::from_iter([("Brightness".to_string(), default)]),
+        )]);
+        let report = compact(&mut save, &defaults)?;
+        assert!(report.compacted.is_empty());
+        let tinyrick = save.items[0].tinyrick.as_ref().unwrap();
+        assert!(tinyrick.properties.contains_key("Brightness"));
+        Ok(())
+    }
+}
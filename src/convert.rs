@@ -0,0 +1,210 @@
+//! Formats other than json.
+//!
+//! The text formats go through the same `serde_json::Value` the json export uses, so all
+//! of them share its layout, and `lossless` and the other json dialects work with them too.
+//! Comments in yaml or toml are not kept, they are gone once the file is read.
+//!
+//! MessagePack and CBOR hold the `SuiteBro` itself, written with its serde derives. They
+//! have the json's fields and floats bit for bit, but guids are written as bytes.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use serde_json::Value;
+
+use crate::suitebro::SuiteBro;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// the game's binary save
+    Save,
+    Json,
+    Yaml,
+    Ron,
+    Toml,
+    /// see `xml` for the layout
+    Xml,
+    MessagePack,
+    Cbor,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "save" => Ok(Format::Save),
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "ron" => Ok(Format::Ron),
+            "toml" => Ok(Format::Toml),
+            "xml" => Ok(Format::Xml),
+            "msgpack" | "mp" => Ok(Format::MessagePack),
+            "cbor" => Ok(Format::Cbor),
+            _ => bail!(
+                "unknown format `{s}`, expected save, json, yaml, ron, toml, xml, msgpack or cbor"
+            ),
+        }
+    }
+}
+
+impl Format {
+    /// Guesses the format from the extension, saves usually have none.
+    pub fn from_path(path: &Path) -> Format {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.to_ascii_lowercase().parse().ok())
+            .unwrap_or(Format::Save)
+    }
+
+    /// MessagePack and CBOR, which hold the save rather than its json.
+    pub fn is_binary(self) -> bool {
+        matches!(self, Format::MessagePack | Format::Cbor)
+    }
+}
+
+/// Toml has no null, absent members read back as `None` all the same.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.into_iter().map(without_nulls).collect()),
+        value => value,
+    }
+}
+
+/// Reads `input` as json, the binary formats as the lossless json of the save they hold.
+pub fn parse(format: Format, input: &[u8]) -> anyhow::Result<Value> {
+    let text = || std::str::from_utf8(input).context("expected utf-8 text");
+    Ok(match format {
+        Format::Save => bail!("a save has its own layout, read it with `SuiteBro::read`"),
+        Format::Json => serde_json::from_slice(input)?,
+        Format::Yaml => serde_yaml::from_slice(input)?,
+        Format::Ron => ron::de::from_bytes(input)?,
+        Format::Toml => toml::from_str(text()?)?,
+        Format::Xml => crate::xml::from_xml(text()?)?,
+        Format::MessagePack | Format::Cbor => {
+            crate::lossless::to_value(&parse_save(format, input)?)?
+        }
+    })
+}
+
+pub fn print(format: Format, value: &Value) -> anyhow::Result<Vec<u8>> {
+    Ok(match format {
+        Format::Save => bail!("a save has its own layout, write it with `SuiteBro::write`"),
+        Format::Json => serde_json::to_vec_pretty(value)?,
+        Format::Yaml => serde_yaml::to_string(value)?.into_bytes(),
+        Format::Ron => {
+            ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?.into_bytes()
+        }
+        Format::Toml => toml::to_string_pretty(&without_nulls(value.clone()))
+            .context("toml can't hold every save, nulls in arrays for one")?
+            .into_bytes(),
+        Format::Xml => crate::xml::to_xml(value)?.into_bytes(),
+        Format::MessagePack | Format::Cbor => {
+            bail!("{format:?} holds the save itself, write it with `print_save`")
+        }
+    })
+}
+
+pub fn parse_save(format: Format, input: &[u8]) -> anyhow::Result<SuiteBro> {
+    Ok(match format {
+        Format::MessagePack => rmp_serde::from_slice(input)?,
+        Format::Cbor => ciborium::de::from_reader(input)?,
+        format => bail!("{format:?} is read as json, read it with `parse`"),
+    })
+}
+
+pub fn print_save(format: Format, save: &SuiteBro) -> anyhow::Result<Vec<u8>> {
+    Ok(match format {
+        Format::MessagePack => rmp_serde::to_vec_named(save)?,
+        Format::Cbor => {
+            let mut output = vec![];
+            ciborium::ser::into_writer(save, &mut output)?;
+            output
+        }
+        format => bail!("{format:?} is written from json, write it with `print`"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::one_item;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Format::from_path(Path::new("CondoData")), Format::Save);
+        assert_eq!(Format::from_path(Path::new("a/save.YML")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("save.ron")), Format::Ron);
+        assert_eq!(Format::from_path(Path::new("save.bin")), Format::Save);
+        assert_eq!(Format::from_path(Path::new("save.cbor")), Format::Cbor);
+    }
+
+    #[test]
+    fn test_round_trip() -> anyhow::Result<()> {
+        let save = one_item();
+        let json = serde_json::to_value(&save)?;
+
+        for format in [
+            Format::Json,
+            Format::Yaml,
+            Format::Ron,
+            Format::Toml,
+            Format::Xml,
+        ] {
+            let output = print(format, &json)?;
+            let read: SuiteBro = serde_json::from_value(parse(format, &output)?)?;
+            assert_eq!(read, save, "{format:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_same_as_json() -> anyhow::Result<()> {
+        let save = one_item();
+        let json = serde_json::to_value(&save)?;
+        let json_len = serde_json::to_vec(&json)?.len();
+
+        let from_json: SuiteBro = serde_json::from_value(json.clone())?;
+
+        for format in [Format::MessagePack, Format::Cbor] {
+            let output = print_save(format, &save)?;
+            assert!(output.len() < json_len, "{format:?}");
+            assert_eq!(parse_save(format, &output)?, from_json, "{format:?}");
+            assert_eq!(parse(format, &output)?, json, "{format:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_non_finite() -> anyhow::Result<()> {
+        let mut save = one_item();
+        save.items[0].position.x = f32::from_bits(0x7fc0_0001);
+        save.items[0].scale.y = f32::NEG_INFINITY;
+
+        for format in [Format::MessagePack, Format::Cbor] {
+            let output = print_save(format, &save)?;
+            let read = parse_save(format, &output)?;
+            assert_eq!(
+                read.items[0].position.x.to_bits(),
+                0x7fc0_0001,
+                "{format:?}"
+            );
+            assert_eq!(read.items[0].scale.y, f32::NEG_INFINITY, "{format:?}");
+
+            let json = parse(format, &output)?;
+            assert_eq!(
+                json["items"][0]["position"]["x"], "#f32:7fc00001",
+                "{format:?}"
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::lossless;
+use crate::suitebro::item::Item;
+use crate::suitebro::SuiteBro;
+use crate::transform::Tolerance;
+
+/// Items sharing a class that sit on top of each other.
+///
+/// `indices` point into `SuiteBro::items`, the first one is the copy that is kept.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub name: String,
+    pub indices: Vec<usize>,
+    /// redundant copies whose tinyrick state isn't the kept one's, removing them loses it
+    pub state_differs: Vec<usize>,
+}
+
+impl DuplicateGroup {
+    pub fn redundant(&self) -> &[usize] {
+        &self.indices[1..]
+    }
+}
+
+/// Whether two items hold the same tinyrick state, compared bit for bit.
+fn same_state(a: &Item, b: &Item) -> bool {
+    match (
+        lossless::to_value(&a.tinyrick),
+        lossless::to_value(&b.tinyrick),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+pub fn find_duplicates(save: &SuiteBro, tolerance: &Tolerance) -> Vec<DuplicateGroup> {
+    let mut by_name: IndexMap<&str, Vec<usize>> = IndexMap::new();
+    for (index, item) in save.items.iter().enumerate() {
+        by_name.entry(&item.name).or_default().push(index);
+    }
+
+    let mut groups = vec![];
+    for (name, indices) in by_name {
+        let mut claimed = vec![false; indices.len()];
+        for (i, &first) in indices.iter().enumerate() {
+            if claimed[i] {
+                continue;
+            }
+            let mut group = vec![first];
+            for (j, &other) in indices.iter().enumerate().skip(i + 1) {
+                if !claimed[j] && tolerance.same_transform(&save.items[first], &save.items[other]) {
+                    claimed[j] = true;
+                    group.push(other);
+                }
+            }
+            if group.len() > 1 {
+                let kept = &save.items[first];
+                let state_differs = group[1..]
+                    .iter()
+                    .copied()
+                    .filter(|&other| !same_state(kept, &save.items[other]))
+                    .collect();
+                groups.push(DuplicateGroup {
+                    name: name.to_string(),
+                    indices: group,
+                    state_differs,
+                });
+            }
+        }
+    }
+    groups
+}
+
+/// Removes every redundant copy listed in `groups`, returning how many items were dropped.
+pub fn remove_duplicates(save: &mut SuiteBro, groups: &[DuplicateGroup]) -> usize {
+    let mut remove = vec![false; save.items.len()];
+    for &index in groups.iter().flat_map(DuplicateGroup::redundant) {
+        remove[index] = true;
+    }
+
+    let before = save.items.len();
+    let mut index = 0;
+    save.items.retain(|_| {
+        let keep = !remove[index];
+        index += 1;
+        keep
+    });
+    before - save.items.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, one_item, save};
+
+    fn at(name: &str, x: f32) -> Item {
+        let mut item = item(name);
+        item.position.x = x;
+        item
+    }
+
+    #[test]
+    fn test_find_and_remove_duplicates() {
+        let mut save = save(vec![
+            at("Chair", 0.0),
+            at("Table", 0.0),
+            at("Chair", 0.05),
+            at("Chair", 50.0),
+            at("Chair", 0.0),
+        ]);
+
+        let groups = find_duplicates(&save, &Tolerance::default());
+        assert_eq!(
+            groups,
+            vec![DuplicateGroup {
+                name: "Chair".to_string(),
+                indices: vec![0, 2, 4],
+                state_differs: vec![],
+            }]
+        );
+
+        assert_eq!(remove_duplicates(&mut save, &groups), 2);
+        let names: Vec<_> = save.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Chair", "Table", "Chair"]);
+    }
+
+    #[test]
+    fn test_state_differs() {
+        let with_state = one_item().items.remove(0);
+        let mut changed = one_item().items.remove(0);
+        changed.tinyrick.as_mut().unwrap().actors.clear();
+        let mut without_state = one_item().items.remove(0);
+        without_state.tinyrick = None;
+        let save = save(vec![
+            with_state,
+            one_item().items.remove(0),
+            changed,
+            without_state,
+        ]);
+
+        let groups = find_duplicates(&save, &Tolerance::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].indices, [0, 1, 2, 3]);
+        assert_eq!(groups[0].state_differs, [2, 3]);
+    }
+}
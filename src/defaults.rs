@@ -0,0 +1,150 @@
+//! Filling in what scripts leave out of the json of a save.
+//!
+//! A missing `guid` gets a new random one, `rotation` becomes identity, `scale` becomes
+//! one, `steam_item_id` becomes 0, and a missing header is written for
+//! [`Defaults::format_version`] and [`Defaults::unreal_version`].
+
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Defaults {
+    pub format_version: u32,
+    pub unreal_version: u32,
+}
+
+impl Default for Defaults {
+    /// the versions the game currently writes
+    fn default() -> Self {
+        Defaults {
+            format_version: 1,
+            unreal_version: 517,
+        }
+    }
+}
+
+/// Inserts `value` at `key` if there's nothing there, recording `path` if it did.
+fn fill(
+    object: &mut Map<String, Value>,
+    key: &str,
+    path: &str,
+    filled: &mut Vec<String>,
+    value: impl FnOnce() -> Value,
+) {
+    if !object.contains_key(key) {
+        object.insert(key.to_string(), value());
+        filled.push(format!("{path}.{key}"));
+    }
+}
+
+/// Fills in everything missing that has a default, returning the paths that were filled.
+///
+/// Parts that are there but of the wrong type are left for `validate` to report.
+pub fn fill_defaults(json: &mut Value, defaults: &Defaults) -> Vec<String> {
+    let mut filled = vec![];
+    let Some(save) = json.as_object_mut() else {
+        return filled;
+    };
+
+    if !save.contains_key("header") {
+        save.insert("header".into(), json!({}));
+    }
+    if let Some(header) = save.get_mut("header").and_then(Value::as_object_mut) {
+        fill(header, "format_version", "header", &mut filled, || {
+            defaults.format_version.into()
+        });
+        fill(header, "unreal_version", "header", &mut filled, || {
+            defaults.unreal_version.into()
+        });
+    }
+    for key in ["items", "properties", "groups"] {
+        if !save.contains_key(key) {
+            save.insert(key.into(), json!([]));
+            filled.push(key.to_string());
+        }
+    }
+
+    if let Some(Value::Array(items)) = save.get_mut("items") {
+        for (i, item) in items.iter_mut().enumerate() {
+            let Some(item) = item.as_object_mut() else {
+                continue;
+            };
+            let path = format!("items[{i}]");
+            fill(item, "guid", &path, &mut filled, || {
+                uuid::Uuid::new_v4().to_string().into()
+            });
+            fill(item, "steam_item_id", &path, &mut filled, || 0.into());
+            fill(
+                item,
+                "rotation",
+                &path,
+                &mut filled,
+                || json!({ "x": 0.0, "y": 0.0, "z": 0.0, "w": 1.0 }),
+            );
+            fill(
+                item,
+                "scale",
+                &path,
+                &mut filled,
+                || json!({ "x": 1.0, "y": 1.0, "z": 1.0 }),
+            );
+        }
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::SuiteBro;
+
+    #[test]
+    fn test_fill_defaults() -> anyhow::Result<()> {
+        let mut json = json!({
+            "items": [
+                { "name": "Chair", "position": { "x": 1.0, "y": 2.0, "z": 3.0 } },
+                {
+                    "name": "Lamp",
+                    "guid": "00000000-0000-0000-0000-000000000001",
+                    "steam_item_id": 7,
+                    "rotation": { "pitch": 0.0, "yaw": 90.0, "roll": 0.0 },
+                    "position": { "x": 0.0, "y": 0.0, "z": 0.0 },
+                    "scale": { "x": 2.0, "y": 2.0, "z": 2.0 },
+                },
+            ],
+        });
+        let defaults = Defaults {
+            format_version: 1,
+            unreal_version: 600,
+        };
+
+        let filled = fill_defaults(&mut json, &defaults);
+        assert_eq!(
+            filled,
+            [
+                "header.format_version",
+                "header.unreal_version",
+                "properties",
+                "groups",
+                "items[0].guid",
+                "items[0].steam_item_id",
+                "items[0].rotation",
+                "items[0].scale",
+            ]
+        );
+
+        let save: SuiteBro = serde_json::from_value(json.clone())?;
+        assert_eq!(save.header.unreal_version, 600);
+        let chair = &save.items[0];
+        assert!(!chair.guid.is_nil());
+        assert_eq!(chair.steam_item_id, 0);
+        assert_eq!(chair.rotation.w, 1.0);
+        assert_eq!(chair.scale.x, 1.0);
+        assert_eq!(chair.position.z, 3.0);
+        assert_eq!(save.items[1].scale.x, 2.0);
+        assert_eq!(save.items[1].steam_item_id, 7);
+
+        // nothing left to fill
+        assert!(fill_defaults(&mut json, &defaults).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,505 @@
+//! Item level differences between two saves, matched by `Item::guid`.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::lossless;
+use crate::suitebro::item::Item;
+use crate::suitebro::SuiteBro;
+use crate::transform::{quat_array, vector_array, Tolerance};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffOptions {
+    pub transform: Tolerance,
+    /// largest difference between two numbers inside properties that is ignored
+    pub value: f64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            transform: Tolerance::default(),
+            value: 1e-6,
+        }
+    }
+}
+
+/// A change to a single value inside the serde representation of a save.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValueChange {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+impl ValueChange {
+    pub fn path(&self) -> &str {
+        match self {
+            ValueChange::Added { path, .. }
+            | ValueChange::Removed { path, .. }
+            | ValueChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for ValueChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueChange::Added { path, value } => write!(f, "{path}: added {value}"),
+            ValueChange::Removed { path, value } => write!(f, "{path}: removed {value}"),
+            ValueChange::Changed { path, from, to } => write!(f, "{path}: {from} -> {to}"),
+        }
+    }
+}
+
+pub(crate) fn join_field(path: &str, field: &str) -> String {
+    match path {
+        "" => field.to_string(),
+        path => format!("{path}.{field}"),
+    }
+}
+
+/// Collects every leaf that differs between `old` and `new` into `changes`.
+///
+/// Numbers within `tolerance` of each other are treated as equal.
+pub fn diff_values(
+    old: &Value,
+    new: &Value,
+    tolerance: f64,
+    path: &str,
+    changes: &mut Vec<ValueChange>,
+) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let path = join_field(path, key);
+                match new.get(key) {
+                    Some(new_value) => diff_values(old_value, new_value, tolerance, &path, changes),
+                    None => changes.push(ValueChange::Removed {
+                        path,
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    changes.push(ValueChange::Added {
+                        path: join_field(path, key),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+                diff_values(
+                    old_value,
+                    new_value,
+                    tolerance,
+                    &format!("{path}[{index}]"),
+                    changes,
+                );
+            }
+            for (index, value) in old.iter().enumerate().skip(new.len()) {
+                changes.push(ValueChange::Removed {
+                    path: format!("{path}[{index}]"),
+                    value: value.clone(),
+                });
+            }
+            for (index, value) in new.iter().enumerate().skip(old.len()) {
+                changes.push(ValueChange::Added {
+                    path: format!("{path}[{index}]"),
+                    value: value.clone(),
+                });
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            let same = match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => (a - b).abs() <= tolerance,
+                _ => a == b,
+            };
+            if !same {
+                changes.push(ValueChange::Changed {
+                    path: path.to_string(),
+                    from: old.clone(),
+                    to: new.clone(),
+                });
+            }
+        }
+        (old, new) if old != new => changes.push(ValueChange::Changed {
+            path: path.to_string(),
+            from: old.clone(),
+            to: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ItemChange {
+    Class { from: String, to: String },
+    WorkshopId { from: u64, to: u64 },
+    Moved { from: [f32; 3], to: [f32; 3] },
+    Rotated { from: [f32; 4], to: [f32; 4] },
+    Rescaled { from: [f32; 3], to: [f32; 3] },
+    StateAdded,
+    StateRemoved,
+    State { changes: Vec<ValueChange> },
+}
+
+impl fmt::Display for ItemChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemChange::Class { from, to } => write!(f, "class {from} -> {to}"),
+            ItemChange::WorkshopId { from, to } => write!(f, "workshop id {from} -> {to}"),
+            ItemChange::Moved { from, to } => write!(f, "moved {from:?} -> {to:?}"),
+            ItemChange::Rotated { from, to } => write!(f, "rotated {from:?} -> {to:?}"),
+            ItemChange::Rescaled { from, to } => write!(f, "rescaled {from:?} -> {to:?}"),
+            ItemChange::StateAdded => write!(f, "state added"),
+            ItemChange::StateRemoved => write!(f, "state removed"),
+            ItemChange::State { changes } => {
+                write!(f, "state changed")?;
+                for change in changes {
+                    write!(f, "\n      {change}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ItemRef {
+    pub index: usize,
+    pub guid: uuid::Uuid,
+    pub name: String,
+}
+
+impl ItemRef {
+    fn new(index: usize, item: &Item) -> Self {
+        ItemRef {
+            index,
+            guid: item.guid,
+            name: item.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModifiedItem {
+    pub old: ItemRef,
+    pub new: ItemRef,
+    pub changes: Vec<ItemChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct SaveDiff {
+    pub added: Vec<ItemRef>,
+    pub removed: Vec<ItemRef>,
+    pub modified: Vec<ModifiedItem>,
+    /// changes to the header, property lists and groups
+    pub other: Vec<ValueChange>,
+    /// items of the old save reusing an earlier item's guid, left out of the diff
+    pub old_duplicates: Vec<ItemRef>,
+    /// items of the new save reusing an earlier item's guid, left out of the diff
+    pub new_duplicates: Vec<ItemRef>,
+}
+
+impl SaveDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.other.is_empty()
+    }
+}
+
+impl fmt::Display for SaveDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.added {
+            writeln!(f, "+ {} {}", item.name, item.guid)?;
+        }
+        for item in &self.removed {
+            writeln!(f, "- {} {}", item.name, item.guid)?;
+        }
+        for item in &self.modified {
+            writeln!(f, "~ {} {}", item.new.name, item.new.guid)?;
+            for change in &item.changes {
+                writeln!(f, "    {change}")?;
+            }
+        }
+        for change in &self.other {
+            writeln!(f, "* {change}")?;
+        }
+        for (save, duplicates) in [("old", &self.old_duplicates), ("new", &self.new_duplicates)] {
+            for item in duplicates {
+                writeln!(
+                    f,
+                    "! {} {} reuses a guid at {save} items[{}], not compared",
+                    item.name, item.guid, item.index
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything that changed on an item that exists in both saves.
+pub fn diff_item(old: &Item, new: &Item, options: &DiffOptions) -> anyhow::Result<Vec<ItemChange>> {
+    let tolerance = &options.transform;
+    let mut changes = vec![];
+    if old.name != new.name {
+        changes.push(ItemChange::Class {
+            from: old.name.clone(),
+            to: new.name.clone(),
+        });
+    }
+    if old.steam_item_id != new.steam_item_id {
+        changes.push(ItemChange::WorkshopId {
+            from: old.steam_item_id,
+            to: new.steam_item_id,
+        });
+    }
+    if !tolerance.same_position(&old.position, &new.position) {
+        changes.push(ItemChange::Moved {
+            from: vector_array(&old.position),
+            to: vector_array(&new.position),
+        });
+    }
+    if !tolerance.same_rotation(&old.rotation, &new.rotation) {
+        changes.push(ItemChange::Rotated {
+            from: quat_array(&old.rotation),
+            to: quat_array(&new.rotation),
+        });
+    }
+    if !tolerance.same_scale(&old.scale, &new.scale) {
+        changes.push(ItemChange::Rescaled {
+            from: vector_array(&old.scale),
+            to: vector_array(&new.scale),
+        });
+    }
+    match (&old.tinyrick, &new.tinyrick) {
+        (None, Some(_)) => changes.push(ItemChange::StateAdded),
+        (Some(_), None) => changes.push(ItemChange::StateRemoved),
+        (Some(old), Some(new)) => {
+            let mut state = vec![];
+            diff_values(
+                &lossless::to_value(old)?,
+                &lossless::to_value(new)?,
+                options.value,
+                "",
+                &mut state,
+            );
+            if !state.is_empty() {
+                changes.push(ItemChange::State { changes: state });
+            }
+        }
+        (None, None) => {}
+    }
+    Ok(changes)
+}
+
+/// Items by guid, keeping the first if a guid is used more than once and returning the
+/// rest as duplicates.
+pub(crate) fn items_by_guid(
+    save: &SuiteBro,
+) -> (IndexMap<uuid::Uuid, (usize, &Item)>, Vec<ItemRef>) {
+    let mut items = IndexMap::new();
+    let mut duplicates = vec![];
+    for (index, item) in save.items.iter().enumerate() {
+        match items.entry(item.guid) {
+            indexmap::map::Entry::Vacant(entry) => {
+                entry.insert((index, item));
+            }
+            indexmap::map::Entry::Occupied(_) => duplicates.push(ItemRef::new(index, item)),
+        }
+    }
+    (items, duplicates)
+}
+
+/// The header, property lists (keyed by name) and groups as one value.
+pub(crate) fn save_metadata(save: &SuiteBro) -> anyhow::Result<Value> {
+    let mut lists = serde_json::Map::new();
+    for list in &save.properties {
+        lists.insert(list.name.clone(), lossless::to_value(&list.properties)?);
+    }
+    Ok(serde_json::json!({
+        "header": lossless::to_value(&save.header)?,
+        "properties": lists,
+        "groups": lossless::to_value(&save.groups)?,
+    }))
+}
+
+pub fn diff(old: &SuiteBro, new: &SuiteBro, options: &DiffOptions) -> anyhow::Result<SaveDiff> {
+    let (old_items, old_duplicates) = items_by_guid(old);
+    let (new_items, new_duplicates) = items_by_guid(new);
+
+    let mut diff = SaveDiff {
+        old_duplicates,
+        new_duplicates,
+        ..Default::default()
+    };
+    for (guid, &(old_index, old_item)) in &old_items {
+        match new_items.get(guid) {
+            Some(&(new_index, new_item)) => {
+                let changes = diff_item(old_item, new_item, options)?;
+                if !changes.is_empty() {
+                    diff.modified.push(ModifiedItem {
+                        old: ItemRef::new(old_index, old_item),
+                        new: ItemRef::new(new_index, new_item),
+                        changes,
+                    });
+                }
+            }
+            None => diff.removed.push(ItemRef::new(old_index, old_item)),
+        }
+    }
+    for (guid, &(new_index, new_item)) in &new_items {
+        if !old_items.contains_key(guid) {
+            diff.added.push(ItemRef::new(new_index, new_item));
+        }
+    }
+
+    diff_values(
+        &save_metadata(old)?,
+        &save_metadata(new)?,
+        options.value,
+        "",
+        &mut diff.other,
+    );
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, save};
+    use serde_json::json;
+
+    fn with_guid(name: &str, guid: u128) -> Item {
+        let mut item = item(name);
+        item.guid = uuid::Uuid::from_u128(guid);
+        item
+    }
+
+    #[test]
+    fn test_diff_values() {
+        let old = json!({ "a": 1.0, "b": [1, 2, 3], "c": "x", "gone": true });
+        let new = json!({ "a": 1.0000001, "b": [1, 5], "c": "y", "new": null });
+        let mut changes = vec![];
+        diff_values(&old, &new, 1e-6, "", &mut changes);
+        assert_eq!(
+            changes,
+            vec![
+                ValueChange::Changed {
+                    path: "b[1]".to_string(),
+                    from: json!(2),
+                    to: json!(5),
+                },
+                ValueChange::Removed {
+                    path: "b[2]".to_string(),
+                    value: json!(3),
+                },
+                ValueChange::Changed {
+                    path: "c".to_string(),
+                    from: json!("x"),
+                    to: json!("y"),
+                },
+                ValueChange::Removed {
+                    path: "gone".to_string(),
+                    value: json!(true),
+                },
+                ValueChange::Added {
+                    path: "new".to_string(),
+                    value: json!(null),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff() -> anyhow::Result<()> {
+        let old = save(vec![
+            with_guid("Chair", 1),
+            with_guid("Table", 2),
+            with_guid("Lamp", 3),
+        ]);
+        let mut new = save(vec![
+            with_guid("Lamp", 3),
+            with_guid("Chair", 1),
+            with_guid("Sofa", 4),
+        ]);
+        new.items[1].position.x = 5.0;
+
+        let diff = diff(&old, &new, &DiffOptions::default())?;
+        let names = |items: &[ItemRef]| -> Vec<(String, usize)> {
+            items.iter().map(|i| (i.name.clone(), i.index)).collect()
+        };
+        assert_eq!(names(&diff.added), [("Sofa".to_string(), 2)]);
+        assert_eq!(names(&diff.removed), [("Table".to_string(), 1)]);
+        // the reordered lamp is unchanged, the chair moved
+        assert_eq!(diff.modified.len(), 1);
+        let chair = &diff.modified[0];
+        assert_eq!((chair.old.index, chair.new.index), (0, 1));
+        assert_eq!(
+            chair.changes,
+            [ItemChange::Moved {
+                from: [0.0; 3],
+                to: [5.0, 0.0, 0.0],
+            }]
+        );
+        assert!(diff.other.is_empty());
+        assert!(diff.new_duplicates.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_duplicate_guids() -> anyhow::Result<()> {
+        let old = save(vec![with_guid("Chair", 1)]);
+        let new = save(vec![with_guid("Chair", 1), with_guid("Table", 1)]);
+
+        let diff = diff(&old, &new, &DiffOptions::default())?;
+        assert!(diff.added.is_empty() && diff.modified.is_empty());
+        assert_eq!(diff.new_duplicates, [ItemRef::new(1, &new.items[1])]);
+        assert!(diff.to_string().contains("reuses a guid at new items[1]"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_non_finite() -> anyhow::Result<()> {
+        use crate::property_path;
+        use crate::suitebro::tests::one_item;
+
+        let mass: property_path::PropertyPath = "items[0].tinyrick\
+            .actors[name=AudioDiffuserPlastic_C_1].properties.PhysicsSettings.MassMultiplier"
+            .parse()?;
+        let mut old = one_item();
+        property_path::set(&mut old, &mass, json!("#f32:7fc00000"))?;
+        let mut new = one_item();
+        property_path::set(&mut new, &mass, json!("#f32:7f800000"))?;
+        new.items[0].rotation.w = f32::NAN;
+
+        // NaN and infinity both are null in plain json, but differ
+        let diff = diff(&old, &new, &DiffOptions::default())?;
+        assert_eq!(diff.modified.len(), 1);
+        let changes = &diff.modified[0].changes;
+        assert!(matches!(changes[0], ItemChange::Rotated { .. }));
+        assert!(matches!(changes[1], ItemChange::State { .. }));
+        Ok(())
+    }
+}
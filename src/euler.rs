@@ -0,0 +1,248 @@
+//! Item rotations as pitch, yaw and roll in degrees.
+//!
+//! The conversions follow Unreal's `FRotator::Quaternion` and `FQuat::Rotator`, so the
+//! angles are the ones shown in the editor. Going through euler angles isn't bit for bit,
+//! a rotation comes back within a small fraction of a degree.
+
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use uesave::Quat;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RotationStyle {
+    /// quaternion components, exactly as stored
+    #[default]
+    Quat,
+    /// pitch, yaw and roll in degrees
+    Euler,
+}
+
+impl FromStr for RotationStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "quat" => Ok(RotationStyle::Quat),
+            "euler" => Ok(RotationStyle::Euler),
+            _ => bail!("unknown rotation style `{s}`, expected quat or euler"),
+        }
+    }
+}
+
+/// Unreal's `FRotator`, in degrees.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Rotator {
+    pub pitch: f64,
+    pub yaw: f64,
+    pub roll: f64,
+}
+
+/// Angles closer than this to straight up or down are treated as gimbal lock.
+const SINGULARITY_THRESHOLD: f64 = 0.4999995;
+
+/// Wraps an angle in degrees to `(-180, 180]`.
+fn normalize_axis(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(360.0);
+    match angle > 180.0 {
+        true => angle - 360.0,
+        false => angle,
+    }
+}
+
+/// `FQuat::Rotator`.
+pub fn quat_to_rotator(q: &Quat) -> Rotator {
+    let (x, y, z, w) = (q.x as f64, q.y as f64, q.z as f64, q.w as f64);
+    let singularity = z * x - w * y;
+    let yaw = (2.0 * (w * z + x * y))
+        .atan2(1.0 - 2.0 * (y * y + z * z))
+        .to_degrees();
+
+    if singularity < -SINGULARITY_THRESHOLD {
+        Rotator {
+            pitch: -90.0,
+            yaw,
+            roll: normalize_axis(-yaw - 2.0 * x.atan2(w).to_degrees()),
+        }
+    } else if singularity > SINGULARITY_THRESHOLD {
+        Rotator {
+            pitch: 90.0,
+            yaw,
+            roll: normalize_axis(yaw - 2.0 * x.atan2(w).to_degrees()),
+        }
+    } else {
+        Rotator {
+            pitch: (2.0 * singularity).asin().to_degrees(),
+            yaw,
+            roll: (-2.0 * (w * x + y * z))
+                .atan2(1.0 - 2.0 * (x * x + y * y))
+                .to_degrees(),
+        }
+    }
+}
+
+/// `FRotator::Quaternion`.
+pub fn rotator_to_quat(r: &Rotator) -> Quat {
+    let (sp, cp) = (r.pitch.to_radians() / 2.0).sin_cos();
+    let (sy, cy) = (r.yaw.to_radians() / 2.0).sin_cos();
+    let (sr, cr) = (r.roll.to_radians() / 2.0).sin_cos();
+    Quat {
+        x: (cr * sp * sy - sr * cp * cy) as f32,
+        y: (-cr * sp * cy - sr * cp * sy) as f32,
+        z: (cr * cp * sy - sr * sp * cy) as f32,
+        w: (cr * cp * cy + sr * sp * sy) as f32,
+    }
+}
+
+/// Rounds away the noise of the f32 components, far below what a quat can tell apart.
+fn round_degrees(angle: f64) -> f64 {
+    let angle = (angle * 1e6).round() / 1e6;
+    // no `-0`
+    angle + 0.0
+}
+
+/// Rewrites the rotation of every item in the json of a save as a [`Rotator`].
+///
+/// Rotations with NaN or infinite components have no angles and are left as they are.
+pub fn to_euler_json(json: &mut Value) {
+    let Some(Value::Array(items)) = json.get_mut("items") else {
+        return;
+    };
+    for item in items {
+        let Some(rotation) = item.get_mut("rotation") else {
+            continue;
+        };
+        let Ok(quat) = serde_json::from_value::<Quat>(rotation.clone()) else {
+            continue;
+        };
+        if ![quat.x, quat.y, quat.z, quat.w]
+            .iter()
+            .all(|c| c.is_finite())
+        {
+            continue;
+        }
+        let rotator = quat_to_rotator(&quat);
+        *rotation = serde_json::json!({
+            "pitch": round_degrees(rotator.pitch),
+            "yaw": round_degrees(rotator.yaw),
+            "roll": round_degrees(rotator.roll),
+        });
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AnyRotation {
+    Quat(Quat),
+    Euler(Rotator),
+}
+
+/// Reads a rotation written either as a quat or as a [`Rotator`].
+pub fn deserialize_rotation<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Quat, D::Error> {
+    Ok(match AnyRotation::deserialize(deserializer)? {
+        AnyRotation::Quat(quat) => quat,
+        AnyRotation::Euler(rotator) => rotator_to_quat(&rotator),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::one_item;
+    use crate::suitebro::SuiteBro;
+    use crate::transform::quat_array;
+
+    fn rotator(pitch: f64, yaw: f64, roll: f64) -> Rotator {
+        Rotator { pitch, yaw, roll }
+    }
+
+    /// `q` and `-q` are the same rotation.
+    fn same_rotation(a: &Quat, b: &Quat, tolerance: f32) -> bool {
+        let (a, b) = (quat_array(a), quat_array(b));
+        let delta = |sign: f32| {
+            a.iter()
+                .zip(b)
+                .all(|(a, b)| (a - sign * b).abs() < tolerance)
+        };
+        delta(1.0) || delta(-1.0)
+    }
+
+    fn assert_quat(found: Quat, expected: [f32; 4]) {
+        let found = [found.x, found.y, found.z, found.w];
+        for (a, b) in found.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-6, "{found:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn test_unreal_axes() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert_quat(
+            rotator_to_quat(&rotator(0.0, 0.0, 0.0)),
+            [0.0, 0.0, 0.0, 1.0],
+        );
+        assert_quat(
+            rotator_to_quat(&rotator(0.0, 90.0, 0.0)),
+            [0.0, 0.0, half, half],
+        );
+        assert_quat(
+            rotator_to_quat(&rotator(90.0, 0.0, 0.0)),
+            [0.0, -half, 0.0, half],
+        );
+        assert_quat(
+            rotator_to_quat(&rotator(0.0, 0.0, 90.0)),
+            [-half, 0.0, 0.0, half],
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let steps = [
+            -179.0, -135.5, -90.0, -45.0, -0.25, 0.0, 12.0, 89.0, 90.0, 135.0, 180.0,
+        ];
+        for pitch in [-89.5, -60.0, -1.0, 0.0, 30.0, 89.5] {
+            for yaw in steps {
+                for roll in steps {
+                    let expected = rotator(pitch, yaw, roll);
+                    let quat = rotator_to_quat(&expected);
+                    let found = quat_to_rotator(&quat);
+                    for (a, b) in [
+                        (found.pitch, expected.pitch),
+                        (found.yaw, expected.yaw),
+                        (found.roll, expected.roll),
+                    ] {
+                        let delta = normalize_axis(a - b).abs();
+                        assert!(delta < 1e-3, "{expected:?} came back as {found:?}");
+                    }
+                    assert!(same_rotation(&quat, &rotator_to_quat(&found), 1e-5));
+                }
+            }
+        }
+
+        // gimbal lock gives different angles for the same rotation
+        for pitch in [-90.0, 90.0] {
+            let quat = rotator_to_quat(&rotator(pitch, 30.0, 10.0));
+            let found = quat_to_rotator(&quat);
+            assert_eq!(found.pitch, pitch);
+            assert!(same_rotation(&quat, &rotator_to_quat(&found), 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_euler_json() -> anyhow::Result<()> {
+        let save = one_item();
+
+        let mut json = serde_json::to_value(&save)?;
+        to_euler_json(&mut json);
+        assert!(json["items"][0]["rotation"].get("yaw").is_some());
+
+        let read: SuiteBro = serde_json::from_value(json)?;
+        for (a, b) in save.items.iter().zip(&read.items) {
+            assert!(same_rotation(&a.rotation, &b.rotation, 1e-5));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Splitting a save into a directory of small json files and back.
+//!
+//! ```text
+//! condo/
+//!   manifest.json           item and property list order
+//!   header.json
+//!   groups.json
+//!   items/<guid>.json       one per item
+//!   properties/<name>.json  one per property list
+//! ```
+//!
+//! The files are written like `to-json --lossless`, so NaN and infinite floats survive.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::Context;
+use indexmap::IndexSet;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::suitebro::item::Item;
+use crate::suitebro::{PropertyList, SuiteBro};
+
+/// File names, relative to their directory, in the order they appear in the save.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Manifest {
+    pub items: Vec<String>,
+    pub properties: Vec<String>,
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> anyhow::Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    crate::lossless::to_writer_pretty(writer, value)
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let reader =
+        BufReader::new(File::open(path).with_context(|| format!("opening {}", path.display()))?);
+    crate::lossless::from_reader(reader).with_context(|| format!("reading {}", path.display()))
+}
+
+/// A file name for `name` that is safe on every platform and not already in `taken`.
+fn unique_file_name(name: &str, taken: &mut IndexSet<String>) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let mut file_name = format!("{stem}.json");
+    let mut n = 1;
+    while taken.contains(&file_name.to_lowercase()) {
+        file_name = format!("{stem}-{n}.json");
+        n += 1;
+    }
+    taken.insert(file_name.to_lowercase());
+    file_name
+}
+
+pub fn explode(save: &SuiteBro, dir: &Path) -> anyhow::Result<()> {
+    let items_dir = dir.join("items");
+    let properties_dir = dir.join("properties");
+    fs::create_dir_all(&items_dir)?;
+    fs::create_dir_all(&properties_dir)?;
+
+    write_json(&dir.join("header.json"), &save.header)?;
+    write_json(&dir.join("groups.json"), &save.groups)?;
+
+    let mut manifest = Manifest::default();
+    let mut taken = IndexSet::new();
+    for item in &save.items {
+        let file_name = unique_file_name(&item.guid.to_string(), &mut taken);
+        write_json(&items_dir.join(&file_name), item)?;
+        manifest.items.push(file_name);
+    }
+
+    let mut taken = IndexSet::new();
+    for list in &save.properties {
+        let file_name = unique_file_name(&list.name, &mut taken);
+        write_json(&properties_dir.join(&file_name), list)?;
+        manifest.properties.push(file_name);
+    }
+
+    write_json(&dir.join("manifest.json"), &manifest)
+}
+
+pub fn implode(dir: &Path) -> anyhow::Result<SuiteBro> {
+    let manifest: Manifest = read_json(&dir.join("manifest.json"))?;
+
+    let items = manifest
+        .items
+        .iter()
+        .map(|file_name| read_json::<Item>(&dir.join("items").join(file_name)))
+        .collect::<anyhow::Result<_>>()?;
+    let properties = manifest
+        .properties
+        .iter()
+        .map(|file_name| read_json::<PropertyList>(&dir.join("properties").join(file_name)))
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(SuiteBro {
+        header: read_json(&dir.join("header.json"))?,
+        items,
+        properties,
+        groups: read_json(&dir.join("groups.json"))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::get_tower_types;
+    use crate::suitebro::tests::{one_item, ONE_ITEM};
+    use std::io::Cursor;
+    use uesave::Writable;
+
+    #[test]
+    fn test_explode_implode() -> anyhow::Result<()> {
+        let save = one_item();
+
+        let dir = std::env::temp_dir().join(format!("suitebro-explode-{}", std::process::id()));
+        explode(&save, &dir)?;
+        let imploded = implode(&dir);
+        fs::remove_dir_all(&dir)?;
+        let imploded = imploded?;
+        assert_eq!(imploded, save);
+
+        let mut output = vec![];
+        uesave::Context::run_with_types(
+            &get_tower_types(),
+            &mut Cursor::new(&mut output),
+            |ctx| imploded.write(ctx),
+        )?;
+        assert_eq!(ONE_ITEM, &output[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite() -> anyhow::Result<()> {
+        let mut save = one_item();
+        save.items[0].rotation.x = f32::NAN;
+        save.items[0].position.y = f32::NEG_INFINITY;
+
+        let dir = std::env::temp_dir().join(format!("suitebro-nan-{}", std::process::id()));
+        explode(&save, &dir)?;
+        let imploded = implode(&dir);
+        fs::remove_dir_all(&dir)?;
+        let item = &imploded?.items[0];
+        assert!(item.rotation.x.is_nan());
+        assert_eq!(item.position.y, f32::NEG_INFINITY);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_file_name() {
+        let mut taken = IndexSet::new();
+        assert_eq!(
+            unique_file_name("Condo/Weather", &mut taken),
+            "Condo_Weather.json"
+        );
+        assert_eq!(
+            unique_file_name("Condo_Weather", &mut taken),
+            "Condo_Weather-1.json"
+        );
+        assert_eq!(
+            unique_file_name("condo_weather", &mut taken),
+            "condo_weather-2.json"
+        );
+    }
+}
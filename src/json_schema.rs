@@ -0,0 +1,366 @@
+//! A JSON Schema (draft-07) for the json `to-json` writes.
+//!
+//! The save's own types are described exactly, and so is the set of uesave's property
+//! variants. Known gaps, only checked for being objects: the values of sets, maps and
+//! bytes, arrays of anything but structs, and the text, field path, soft object, delegate
+//! and raw data variants. Struct values other than the ones listed are accepted as long
+//! as they look like one, `{"Name": {...}}`.
+
+use serde_json::{json, Value};
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{name}") })
+}
+
+/// `{"id": ..., "value": value}` plus any `extra` fields.
+fn property_variant(value: Value, extra: &[(&str, Value)]) -> Value {
+    let mut properties = serde_json::Map::new();
+    properties.insert("id".into(), reference("OptionalGuid"));
+    properties.insert("value".into(), value);
+    for (name, schema) in extra {
+        properties.insert(name.to_string(), schema.clone());
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": ["value"],
+    })
+}
+
+fn property_variants() -> Value {
+    let integer = json!({ "type": "integer" });
+    let string = json!({ "type": ["string", "null"] });
+    let loose = json!({ "type": "object" });
+    let mut variants = serde_json::Map::new();
+    for name in [
+        "Int8", "Int16", "Int", "Int64", "UInt8", "UInt16", "UInt32", "UInt64",
+    ] {
+        variants.insert(name.into(), property_variant(integer.clone(), &[]));
+    }
+    for name in ["Float", "Double"] {
+        variants.insert(name.into(), property_variant(reference("Float"), &[]));
+    }
+    variants.insert(
+        "Bool".into(),
+        property_variant(json!({ "type": "boolean" }), &[]),
+    );
+    for name in ["Str", "Name", "Object"] {
+        variants.insert(name.into(), property_variant(string.clone(), &[]));
+    }
+    variants.insert(
+        "Enum".into(),
+        property_variant(string.clone(), &[("enum_type", string.clone())]),
+    );
+    variants.insert(
+        "Byte".into(),
+        property_variant(loose.clone(), &[("enum_type", string.clone())]),
+    );
+    variants.insert(
+        "Struct".into(),
+        property_variant(
+            reference("StructValue"),
+            &[
+                ("struct_type", reference("StructType")),
+                ("struct_id", reference("Guid")),
+            ],
+        ),
+    );
+    variants.insert(
+        "Array".into(),
+        property_variant(
+            reference("ArrayValue"),
+            &[("array_type", json!({ "type": "string" }))],
+        ),
+    );
+    for name in ["Set", "Map"] {
+        variants.insert(name.into(), property_variant(loose.clone(), &[]));
+    }
+    for name in [
+        "Text",
+        "FieldPath",
+        "SoftObject",
+        "Delegate",
+        "MulticastDelegate",
+        "MulticastInlineDelegate",
+        "MulticastSparseDelegate",
+        "RawData",
+    ] {
+        variants.insert(name.into(), loose.clone());
+    }
+    Value::Object(variants)
+}
+
+/// `{"Tag": value}`, with known tags checked against `variants`.
+fn tagged(variants: Value) -> Value {
+    json!({
+        "type": "object",
+        "minProperties": 1,
+        "maxProperties": 1,
+        "propertyNames": { "pattern": "^[A-Z][A-Za-z0-9]*$" },
+        "properties": variants,
+    })
+}
+
+/// `{"Tag": value}` with one of the tags in `variants` only.
+fn closed(variants: Value) -> Value {
+    json!({
+        "type": "object",
+        "minProperties": 1,
+        "maxProperties": 1,
+        "properties": variants,
+        "additionalProperties": false,
+    })
+}
+
+fn vector(names: &[&str], component: Value) -> Value {
+    let properties: serde_json::Map<_, _> = names
+        .iter()
+        .map(|name| (name.to_string(), component.clone()))
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": names,
+        "additionalProperties": false,
+    })
+}
+
+pub fn suitebro_schema() -> Value {
+    let float = reference("Float");
+    let color = vector(&["r", "g", "b", "a"], float.clone());
+    let struct_values = json!({
+        "Struct": reference("Properties"),
+        "LinearColor": color,
+        "Color": vector(&["r", "g", "b", "a"], json!({ "type": "integer" })),
+        "Vector": reference("Vector"),
+        "Quat": reference("Quat"),
+        "Guid": reference("Guid"),
+    });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "SuiteBro",
+        "description": "A Tower Unite condo or workshop save, as written by `to-json`",
+        "type": "object",
+        "properties": {
+            "header": reference("Header"),
+            "items": { "type": "array", "items": reference("Item") },
+            "properties": { "type": "array", "items": reference("PropertyList") },
+            "groups": { "type": "array", "items": reference("GroupInfo") },
+        },
+        "required": ["header", "items", "properties", "groups"],
+        "additionalProperties": false,
+        "definitions": {
+            "Header": {
+                "type": "object",
+                "properties": {
+                    "format_version": reference("U32"),
+                    "unreal_version": reference("U32"),
+                },
+                "required": ["format_version", "unreal_version"],
+                "additionalProperties": false,
+            },
+            "Item": {
+                "description": "An item, the fields of `TinyRick` are flattened into it when it has state",
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "guid": reference("Guid"),
+                    "steam_item_id": { "type": "integer", "minimum": 0 },
+                    "format_version": reference("U32"),
+                    "unreal_version": reference("U32"),
+                    "properties": reference("Properties"),
+                    "actors": { "type": "array", "items": reference("ActorInfo") },
+                    "rotation": { "oneOf": [reference("Quat"), reference("Rotator")] },
+                    "position": reference("Vector"),
+                    "scale": reference("Vector"),
+                },
+                "required": ["name", "guid", "steam_item_id", "rotation", "position", "scale"],
+                "dependencies": {
+                    "properties": ["format_version", "unreal_version", "actors"],
+                },
+                "additionalProperties": false,
+            },
+            "ActorInfo": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "properties": reference("Properties"),
+                },
+                "required": ["name", "properties"],
+                "additionalProperties": false,
+            },
+            "PropertyList": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "properties": reference("Properties"),
+                },
+                "required": ["name", "properties"],
+                "additionalProperties": false,
+            },
+            "GroupInfo": {
+                "type": "object",
+                "properties": {
+                    "item_count": reference("U32"),
+                    "group_id": reference("U32"),
+                },
+                "required": ["item_count", "group_id"],
+                "additionalProperties": false,
+            },
+            "Properties": {
+                "description": "uesave properties by name",
+                "type": "object",
+                "additionalProperties": reference("Property"),
+            },
+            "Property": closed(property_variants()),
+            "StructValue": tagged(struct_values),
+            "StructType": {
+                "description": "`LinearColor` and the like, `{\"Struct\": name}` for the rest",
+                "anyOf": [
+                    { "type": "string" },
+                    closed(json!({ "Struct": { "type": ["string", "null"] } })),
+                ],
+            },
+            "ArrayValue": closed(json!({
+                "Base": { "type": "object" },
+                "Struct": {
+                    "type": "object",
+                    "properties": {
+                        "_type": { "type": "string" },
+                        "name": { "type": "string" },
+                        "struct_type": reference("StructType"),
+                        "id": reference("Guid"),
+                        "value": { "type": "array", "items": reference("StructValue") },
+                    },
+                    "required": ["struct_type", "value"],
+                    "additionalProperties": false,
+                },
+            })),
+            "Quat": vector(&["x", "y", "z", "w"], float.clone()),
+            "Vector": vector(&["x", "y", "z"], float.clone()),
+            "Rotator": {
+                "description": "degrees, see `to-json --rotation euler`",
+                "type": "object",
+                "properties": {
+                    "pitch": { "type": "number" },
+                    "yaw": { "type": "number" },
+                    "roll": { "type": "number" },
+                },
+                "required": ["pitch", "yaw", "roll"],
+                "additionalProperties": false,
+            },
+            "Float": {
+                "description": "null is NaN or infinity, strings are `to-json --lossless` bit patterns",
+                "anyOf": [
+                    { "type": "number" },
+                    { "type": "null" },
+                    { "type": "string", "pattern": "^#f(32|64):[0-9a-f]+$" },
+                ],
+            },
+            "U32": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+            "Guid": {
+                "type": "string",
+                "pattern": "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+            },
+            "OptionalGuid": { "anyOf": [reference("Guid"), { "type": "null" }] },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euler;
+    use crate::lossless;
+    use crate::suitebro::tests::one_item;
+    use crate::suitebro::{get_tower_types, SuiteBro};
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::Path;
+    use uesave::Readable;
+
+    fn assert_valid(schema: &jsonschema::JSONSchema, json: &Value, name: &str) {
+        if let Err(errors) = schema.validate(json) {
+            let errors: Vec<_> = errors
+                .map(|e| format!("{}: {e}", e.instance_path))
+                .collect();
+            panic!("{name} doesn't match the schema:\n{}", errors.join("\n"));
+        }
+    }
+
+    #[test]
+    fn test_fixtures_validate() -> anyhow::Result<()> {
+        let schema = suitebro_schema();
+        let schema = jsonschema::JSONSchema::compile(&schema)
+            .map_err(|e| anyhow::anyhow!("invalid schema: {e}"))?;
+
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        for entry in fs::read_dir(assets)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path.display().to_string();
+            let input = fs::read(&path)?;
+            let save = uesave::Context::run_with_types(
+                &get_tower_types(),
+                &mut Cursor::new(&input[..]),
+                SuiteBro::read,
+            )?;
+
+            let mut json = serde_json::to_value(&save)?;
+            assert_valid(&schema, &json, &name);
+            assert_valid(&schema, &lossless::to_value(&save)?, &name);
+            euler::to_euler_json(&mut json);
+            assert_valid(&schema, &json, &name);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects() -> anyhow::Result<()> {
+        let schema = suitebro_schema();
+        let schema = jsonschema::JSONSchema::compile(&schema)
+            .map_err(|e| anyhow::anyhow!("invalid schema: {e}"))?;
+
+        let save = one_item();
+        let json = serde_json::to_value(&save)?;
+
+        let mut broken = json.clone();
+        broken["items"][0]["guid"] = "not a guid".into();
+        assert!(!schema.is_valid(&broken));
+
+        let mut broken = json.clone();
+        broken["items"][0]["scale"]["w"] = 1.0.into();
+        assert!(!schema.is_valid(&broken));
+
+        let mut broken = json.clone();
+        broken["header"]["format_version"] = (-1).into();
+        assert!(!schema.is_valid(&broken));
+
+        let mut broken = json.clone();
+        broken["items"][0]["properties"]["Made"] = json!({ "Made": { "value": 1 } });
+        assert!(!schema.is_valid(&broken));
+
+        let mut broken = json.clone();
+        broken["items"][0]["properties"]["Tint"] = json!({ "Struct": {
+            "id": null,
+            "value": { "LinearColor": { "r": 1.0, "g": 1.0, "b": 1.0, "a": 1.0 } },
+            "struct_type": 7,
+            "struct_id": "00000000-0000-0000-0000-000000000000",
+        } });
+        assert!(!schema.is_valid(&broken));
+        broken["items"][0]["properties"]["Tint"]["Struct"]["struct_type"] = "LinearColor".into();
+        assert!(schema.is_valid(&broken));
+
+        let mut broken = json;
+        broken["items"][0]["properties"]["Scores"] = json!({ "Array": {
+            "array_type": "IntProperty",
+            "id": null,
+            "value": [1, 2],
+        } });
+        assert!(!schema.is_valid(&broken));
+        Ok(())
+    }
+}
@@ -1,7 +1,32 @@
 #![feature(seek_stream_len)]
 
+pub mod bom;
 pub mod byte_size;
+pub mod compact;
+pub mod convert;
+pub mod dedupe;
+pub mod defaults;
+pub mod diff;
+pub mod euler;
+pub mod explode;
+pub mod json_schema;
+pub mod lint;
+pub mod lossless;
+pub mod merge;
+pub mod property_path;
+pub mod recolor;
+pub mod replace;
+pub mod roundtrip;
+pub mod sanitize;
+pub mod simple;
+pub mod size;
+pub mod stats;
 pub mod suitebro;
+pub mod textconv;
+pub mod transform;
+pub mod validate;
+pub mod workshop;
+pub mod xml;
 
 #[cfg(test)]
 mod tests {
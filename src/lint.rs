@@ -0,0 +1,498 @@
+//! Health checks for a save, configured from toml.
+//!
+//! ```toml
+//! [rules.rotation]
+//! severity = "error"
+//! tolerance = 0.001
+//!
+//! [rules.outlier]
+//! enabled = false
+//!
+//! [rules.class_cap]
+//! default = 500
+//! caps = { "CanvasPainting" = 20 }
+//! ```
+//!
+//! Every rule is enabled with its default severity unless the config says otherwise.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::stats::Bounds;
+use crate::suitebro::SuiteBro;
+use crate::transform::{quat_array, quat_length, vector_array};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => bail!("unknown severity `{s}`, expected info, warning or error"),
+        }
+    }
+}
+
+/// A problem with one item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub item: usize,
+    pub message: String,
+}
+
+pub trait Rule {
+    fn name(&self) -> &'static str;
+
+    fn default_severity(&self) -> Severity;
+
+    fn check(&self, save: &SuiteBro) -> Vec<Issue>;
+}
+
+/// More than one item with the same guid.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DuplicateGuid {}
+
+impl Rule for DuplicateGuid {
+    fn name(&self) -> &'static str {
+        "duplicate_guid"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, save: &SuiteBro) -> Vec<Issue> {
+        let mut first = IndexMap::new();
+        let mut issues = vec![];
+        for (index, item) in save.items.iter().enumerate() {
+            match first.get(&item.guid) {
+                Some(other) => issues.push(Issue {
+                    item: index,
+                    message: format!("guid {} is also used by items[{other}]", item.guid),
+                }),
+                None => {
+                    first.insert(item.guid, index);
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Rotations that aren't finite or aren't unit quaternions.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rotation {
+    /// how far the length may be from 1
+    pub tolerance: f32,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Rotation { tolerance: 0.01 }
+    }
+}
+
+impl Rule for Rotation {
+    fn name(&self) -> &'static str {
+        "rotation"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, save: &SuiteBro) -> Vec<Issue> {
+        let mut issues = vec![];
+        for (index, item) in save.items.iter().enumerate() {
+            let rotation = quat_array(&item.rotation);
+            let message = if !rotation.iter().all(|c| c.is_finite()) {
+                format!("rotation {rotation:?} isn't finite")
+            } else if (quat_length(&item.rotation) - 1.0).abs() > self.tolerance {
+                format!(
+                    "rotation {rotation:?} has length {}, not 1",
+                    quat_length(&item.rotation)
+                )
+            } else {
+                continue;
+            };
+            issues.push(Issue {
+                item: index,
+                message,
+            });
+        }
+        issues
+    }
+}
+
+/// Scale components that are zero, negative or not finite.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scale {}
+
+impl Rule for Scale {
+    fn name(&self) -> &'static str {
+        "scale"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, save: &SuiteBro) -> Vec<Issue> {
+        let mut issues = vec![];
+        for (index, item) in save.items.iter().enumerate() {
+            let scale = vector_array(&item.scale);
+            // written so NaN fails the check too
+            if !scale.iter().all(|c| c.is_finite() && *c > 0.0) {
+                issues.push(Issue {
+                    item: index,
+                    message: format!("scale {scale:?} isn't positive"),
+                });
+            }
+        }
+        issues
+    }
+}
+
+/// Items far away from where most of the save is.
+///
+/// The box the items are compared against leaves out the outermost `percentile` of
+/// positions on every axis, so a single stray item can't stretch it to include itself.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Outlier {
+    /// share of positions left out on each side of every axis
+    pub percentile: f32,
+    /// how far outside the box an item may be, in unreal units
+    pub margin: f32,
+}
+
+impl Default for Outlier {
+    fn default() -> Self {
+        Outlier {
+            percentile: 0.05,
+            margin: 10000.0,
+        }
+    }
+}
+
+impl Outlier {
+    fn bounds(&self, save: &SuiteBro) -> Option<Bounds> {
+        let mut axes: [Vec<f32>; 3] = Default::default();
+        for item in &save.items {
+            let position = vector_array(&item.position);
+            if position.iter().all(|c| c.is_finite()) {
+                for (axis, c) in axes.iter_mut().zip(position) {
+                    axis.push(c);
+                }
+            }
+        }
+        if axes[0].is_empty() {
+            return None;
+        }
+        let last = axes[0].len() - 1;
+        let skip = ((last as f32 * self.percentile.clamp(0.0, 0.5)).round() as usize).min(last / 2);
+        for axis in &mut axes {
+            axis.sort_by(f32::total_cmp);
+        }
+        Some(Bounds {
+            min: [0, 1, 2].map(|i| axes[i][skip]),
+            max: [0, 1, 2].map(|i| axes[i][last - skip]),
+        })
+    }
+}
+
+impl Rule for Outlier {
+    fn name(&self) -> &'static str {
+        "outlier"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, save: &SuiteBro) -> Vec<Issue> {
+        let Some(bounds) = self.bounds(save) else {
+            return vec![];
+        };
+        let mut issues = vec![];
+        for (index, item) in save.items.iter().enumerate() {
+            let position = vector_array(&item.position);
+            if position.iter().all(|c| c.is_finite()) && !bounds.contains(position, self.margin) {
+                issues.push(Issue {
+                    item: index,
+                    message: format!(
+                        "position {position:?} is more than {} units outside {:?} to {:?}",
+                        self.margin, bounds.min, bounds.max
+                    ),
+                });
+            }
+        }
+        issues
+    }
+}
+
+/// More items of a class than allowed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassCap {
+    /// cap for classes not in `caps`, none when missing
+    pub default: Option<usize>,
+    /// cap per class name
+    pub caps: IndexMap<String, usize>,
+}
+
+impl Rule for ClassCap {
+    fn name(&self) -> &'static str {
+        "class_cap"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, save: &SuiteBro) -> Vec<Issue> {
+        let mut counts: IndexMap<&str, usize> = IndexMap::new();
+        let mut issues = vec![];
+        for (index, item) in save.items.iter().enumerate() {
+            let count = counts.entry(&item.name).or_insert(0);
+            *count += 1;
+            let cap = self.caps.get(&item.name).copied().or(self.default);
+            // only the first item over the cap is reported
+            if cap.is_some_and(|cap| *count == cap + 1) {
+                let total = save.items.iter().filter(|i| i.name == item.name).count();
+                issues.push(Issue {
+                    item: index,
+                    message: format!(
+                        "{total} items of {} are over the cap of {}",
+                        item.name,
+                        cap.unwrap()
+                    ),
+                });
+            }
+        }
+        issues
+    }
+}
+
+/// Names of every rule, in the order they run.
+pub const RULES: &[&str] = &[
+    "duplicate_guid",
+    "rotation",
+    "scale",
+    "outlier",
+    "class_cap",
+];
+
+fn parse_rule<R: Rule + DeserializeOwned + 'static>(
+    options: toml::Table,
+) -> anyhow::Result<Box<dyn Rule>> {
+    Ok(Box::new(toml::Value::Table(options).try_into::<R>()?))
+}
+
+fn build_rule(name: &str, options: toml::Table) -> anyhow::Result<Box<dyn Rule>> {
+    match name {
+        "duplicate_guid" => parse_rule::<DuplicateGuid>(options),
+        "rotation" => parse_rule::<Rotation>(options),
+        "scale" => parse_rule::<Scale>(options),
+        "outlier" => parse_rule::<Outlier>(options),
+        "class_cap" => parse_rule::<ClassCap>(options),
+        _ => bail!("unknown lint rule `{name}`"),
+    }
+}
+
+fn yes() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct RuleConfig {
+    #[serde(default = "yes")]
+    pub enabled: bool,
+    pub severity: Option<Severity>,
+    /// the rule's own options
+    #[serde(flatten)]
+    pub options: toml::Table,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub rules: IndexMap<String, RuleConfig>,
+}
+
+impl LintConfig {
+    pub fn from_toml(input: &str) -> anyhow::Result<Self> {
+        let config: LintConfig = toml::from_str(input)?;
+        if let Some(name) = config
+            .rules
+            .keys()
+            .find(|name| !RULES.contains(&name.as_str()))
+        {
+            bail!("unknown lint rule `{name}`");
+        }
+        Ok(config)
+    }
+
+    /// The enabled rules with their severity.
+    pub fn rules(&self) -> anyhow::Result<Vec<(Box<dyn Rule>, Severity)>> {
+        let mut rules = vec![];
+        for name in RULES {
+            let config = self.rules.get(*name).cloned().unwrap_or(RuleConfig {
+                enabled: true,
+                ..Default::default()
+            });
+            if !config.enabled {
+                continue;
+            }
+            let rule = build_rule(name, config.options)
+                .with_context(|| format!("options of lint rule `{name}`"))?;
+            let severity = config.severity.unwrap_or(rule.default_severity());
+            rules.push((rule, severity));
+        }
+        Ok(rules)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub item: usize,
+    pub guid: uuid::Uuid,
+    pub name: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] items[{}] {} {}: {}",
+            self.severity, self.rule, self.item, self.name, self.guid, self.message
+        )
+    }
+}
+
+/// Runs every enabled rule, findings are ordered by rule then item.
+pub fn lint(save: &SuiteBro, config: &LintConfig) -> anyhow::Result<Vec<Finding>> {
+    let mut findings = vec![];
+    for (rule, severity) in config.rules()? {
+        for issue in rule.check(save) {
+            let item = &save.items[issue.item];
+            findings.push(Finding {
+                rule: rule.name(),
+                severity,
+                item: issue.item,
+                guid: item.guid,
+                name: item.name.clone(),
+                message: issue.message,
+            });
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::item;
+
+    fn save() -> SuiteBro {
+        let mut items: Vec<_> = (0..20)
+            .map(|i| {
+                let mut item = item("Chair");
+                item.guid = uuid::Uuid::from_u128(i);
+                item.position.x = i as f32;
+                item
+            })
+            .collect();
+        items[3].guid = items[1].guid;
+        items[4].rotation.w = f32::NAN;
+        items[5].rotation.w = 2.0;
+        items[6].scale.y = 0.0;
+        items[7].position.x = 1e6;
+        crate::suitebro::tests::save(items)
+    }
+
+    fn found(findings: &[Finding]) -> Vec<(&str, usize)> {
+        findings.iter().map(|f| (f.rule, f.item)).collect()
+    }
+
+    #[test]
+    fn test_default_rules() -> anyhow::Result<()> {
+        let findings = lint(&save(), &LintConfig::default())?;
+        assert_eq!(
+            found(&findings),
+            [
+                ("duplicate_guid", 3),
+                ("rotation", 4),
+                ("rotation", 5),
+                ("scale", 6),
+                ("outlier", 7)
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Error);
+        Ok(())
+    }
+
+    #[test]
+    fn test_config() -> anyhow::Result<()> {
+        let config = LintConfig::from_toml(
+            r#"
+            [rules.duplicate_guid]
+            enabled = false
+
+            [rules.rotation]
+            severity = "info"
+            tolerance = 2.0
+
+            [rules.outlier]
+            enabled = false
+
+            [rules.class_cap]
+            caps = { "Chair" = 10 }
+            "#,
+        )?;
+        let findings = lint(&save(), &config)?;
+        assert_eq!(
+            found(&findings),
+            [("rotation", 4), ("scale", 6), ("class_cap", 10)]
+        );
+        assert_eq!(findings[0].severity, Severity::Info);
+
+        assert!(LintConfig::from_toml("[rules.nope]").is_err());
+        assert!(LintConfig::from_toml("[rules.scale]\nmargin = 1")
+            .unwrap()
+            .rules()
+            .is_err());
+        Ok(())
+    }
+}
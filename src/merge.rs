@@ -0,0 +1,452 @@
+//! Three-way merge of saves, matching items by `Item::guid`.
+//!
+//! Items, property lists and the header are merged through their lossless json, see
+//! `lossless`.
+//! A value changed on only one side takes that side, objects changed on both sides are
+//! merged key by key, and anything else changed on both sides is a conflict. An item's
+//! rotation, position and scale are never merged part by part.
+//!
+//! Items that reuse a guid are matched by the guid and how many items before them have it.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::diff::{diff_values, join_field};
+use crate::lossless;
+use crate::suitebro::item::Item;
+use crate::suitebro::SuiteBro;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MergeOptions {
+    /// largest difference between two numbers that still counts as unchanged
+    pub tolerance: f64,
+    /// put conflict markers in the merged json instead of taking our side
+    pub markers: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            tolerance: 1e-6,
+            markers: false,
+        }
+    }
+}
+
+pub const OURS_MARKER: &str = "<<<<<<< ours";
+pub const BASE_MARKER: &str = "||||||| base";
+pub const THEIRS_MARKER: &str = ">>>>>>> theirs";
+
+/// Item fields that only make sense as a whole, a rotation with one side's `x` and the
+/// other's `w` isn't either of them.
+const TRANSFORM_FIELDS: &[&str] = &["rotation", "position", "scale"];
+
+/// A value changed differently on both sides. `None` means the value was removed.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Conflict {
+    pub path: String,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    /// the merged save in the same form as `to-json --lossless` writes it
+    pub json: Value,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    /// Fails if the merge was done with conflict markers and there were conflicts.
+    pub fn into_save(self) -> anyhow::Result<SuiteBro> {
+        Ok(lossless::from_value(self.json)?)
+    }
+}
+
+struct Merger {
+    options: MergeOptions,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger {
+    fn same(&self, a: Option<&Value>, b: Option<&Value>) -> bool {
+        match (a, b) {
+            (None, None) => true,
+            (Some(a), Some(b)) => {
+                let mut changes = vec![];
+                diff_values(a, b, self.options.tolerance, "", &mut changes);
+                changes.is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// The value to take when at most one side changed it.
+    fn one_sided<'a>(
+        &self,
+        base: Option<&Value>,
+        ours: Option<&'a Value>,
+        theirs: Option<&'a Value>,
+    ) -> Option<Option<&'a Value>> {
+        if self.same(ours, theirs) || self.same(base, theirs) {
+            return Some(ours);
+        }
+        if self.same(base, ours) {
+            return Some(theirs);
+        }
+        None
+    }
+
+    fn conflict(
+        &mut self,
+        path: &str,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        self.conflicts.push(Conflict {
+            path: path.to_string(),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        });
+        match self.options.markers {
+            true => {
+                let mut markers = Map::new();
+                for (marker, value) in [
+                    (OURS_MARKER, ours),
+                    (BASE_MARKER, base),
+                    (THEIRS_MARKER, theirs),
+                ] {
+                    markers.insert(marker.to_string(), value.cloned().unwrap_or(Value::Null));
+                }
+                Some(Value::Object(markers))
+            }
+            false => ours.cloned(),
+        }
+    }
+
+    fn merge(
+        &mut self,
+        path: &str,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        if let Some(side) = self.one_sided(base, ours, theirs) {
+            return side.cloned();
+        }
+
+        let empty = Map::new();
+        if let (Some(Value::Object(o)), Some(Value::Object(t))) = (ours, theirs) {
+            let b = match base {
+                Some(Value::Object(b)) => Some(b),
+                None => Some(&empty),
+                Some(_) => None,
+            };
+            if let Some(b) = b {
+                return Some(Value::Object(self.merge_objects(path, b, o, t, &[])));
+            }
+        }
+        self.conflict(path, base, ours, theirs)
+    }
+
+    /// Like `merge`, but an item's transform is taken from one side as a whole.
+    fn merge_item(
+        &mut self,
+        path: &str,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        if let Some(side) = self.one_sided(base, ours, theirs) {
+            return side.cloned();
+        }
+        match (base, ours, theirs) {
+            (Some(Value::Object(b)), Some(Value::Object(o)), Some(Value::Object(t))) => Some(
+                Value::Object(self.merge_objects(path, b, o, t, TRANSFORM_FIELDS)),
+            ),
+            _ => self.conflict(path, base, ours, theirs),
+        }
+    }
+
+    /// Merges key by key, the values at `whole` are never merged part by part.
+    fn merge_objects(
+        &mut self,
+        path: &str,
+        base: &Map<String, Value>,
+        ours: &Map<String, Value>,
+        theirs: &Map<String, Value>,
+        whole: &[&str],
+    ) -> Map<String, Value> {
+        let mut keys: Vec<&String> = ours.keys().collect();
+        keys.extend(theirs.keys().filter(|k| !ours.contains_key(*k)));
+        keys.extend(
+            base.keys()
+                .filter(|k| !ours.contains_key(*k) && !theirs.contains_key(*k)),
+        );
+
+        let mut merged = Map::new();
+        for key in keys {
+            let path = join_field(path, key);
+            let (b, o, t) = (base.get(key), ours.get(key), theirs.get(key));
+            let value = match whole.contains(&key.as_str()) {
+                true => match self.one_sided(b, o, t) {
+                    Some(side) => side.cloned(),
+                    None => self.conflict(&path, b, o, t),
+                },
+                false => self.merge(&path, b, o, t),
+            };
+            if let Some(value) = value {
+                merged.insert(key.clone(), value);
+            }
+        }
+        merged
+    }
+}
+
+/// An item without its guid, with the tinyrick state nested instead of flattened.
+fn item_value(item: &Item) -> anyhow::Result<Value> {
+    Ok(json!({
+        "name": item.name,
+        "steam_item_id": item.steam_item_id,
+        "tinyrick": lossless::to_value(&item.tinyrick)?,
+        "rotation": lossless::to_value(&item.rotation)?,
+        "position": lossless::to_value(&item.position)?,
+        "scale": lossless::to_value(&item.scale)?,
+    }))
+}
+
+/// Turns a merged `item_value` back into the layout `Item` is serialized with.
+fn item_json(guid: uuid::Uuid, value: Value) -> Value {
+    let Value::Object(mut fields) = value else {
+        return value;
+    };
+    fn take(fields: &mut Map<String, Value>, item: &mut Map<String, Value>, key: &str) {
+        if let Some(value) = fields.remove(key) {
+            item.insert(key.to_string(), value);
+        }
+    }
+
+    let mut item = Map::new();
+    take(&mut fields, &mut item, "name");
+    item.insert("guid".to_string(), json!(guid));
+    take(&mut fields, &mut item, "steam_item_id");
+    match fields.remove("tinyrick") {
+        Some(Value::Object(tinyrick)) if !tinyrick.contains_key(OURS_MARKER) => {
+            item.extend(tinyrick)
+        }
+        Some(Value::Null) | None => {}
+        Some(conflict) => {
+            item.insert("tinyrick".to_string(), conflict);
+        }
+    }
+    take(&mut fields, &mut item, "rotation");
+    take(&mut fields, &mut item, "position");
+    take(&mut fields, &mut item, "scale");
+    item.extend(fields);
+    Value::Object(item)
+}
+
+/// Items by guid and how many items before them share it, so reused guids are kept.
+fn items_by_guid(save: &SuiteBro) -> anyhow::Result<IndexMap<(uuid::Uuid, usize), Value>> {
+    let mut items = IndexMap::new();
+    let mut seen: HashMap<uuid::Uuid, usize> = HashMap::new();
+    for item in &save.items {
+        let occurrence = seen.entry(item.guid).or_default();
+        items.insert((item.guid, *occurrence), item_value(item)?);
+        *occurrence += 1;
+    }
+    Ok(items)
+}
+
+fn property_lists(save: &SuiteBro) -> anyhow::Result<Map<String, Value>> {
+    let mut lists = Map::new();
+    for list in &save.properties {
+        lists.insert(list.name.clone(), lossless::to_value(&list.properties)?);
+    }
+    Ok(lists)
+}
+
+pub fn merge(
+    base: &SuiteBro,
+    ours: &SuiteBro,
+    theirs: &SuiteBro,
+    options: &MergeOptions,
+) -> anyhow::Result<MergeResult> {
+    let mut merger = Merger {
+        options: *options,
+        conflicts: vec![],
+    };
+
+    let header = merger.merge(
+        "header",
+        Some(&lossless::to_value(&base.header)?),
+        Some(&lossless::to_value(&ours.header)?),
+        Some(&lossless::to_value(&theirs.header)?),
+    );
+
+    let (base_items, our_items, their_items) = (
+        items_by_guid(base)?,
+        items_by_guid(ours)?,
+        items_by_guid(theirs)?,
+    );
+    let mut keys: Vec<&(uuid::Uuid, usize)> = our_items.keys().collect();
+    keys.extend(their_items.keys().filter(|k| !our_items.contains_key(*k)));
+    let mut items = vec![];
+    for key in keys {
+        let (guid, occurrence) = *key;
+        let path = match occurrence {
+            0 => format!("items[guid={guid}]"),
+            n => format!("items[guid={guid}#{n}]"),
+        };
+        let merged = merger.merge_item(
+            &path,
+            base_items.get(key),
+            our_items.get(key),
+            their_items.get(key),
+        );
+        if let Some(merged) = merged {
+            items.push(item_json(guid, merged));
+        }
+    }
+
+    let lists = merger.merge_objects(
+        "properties",
+        &property_lists(base)?,
+        &property_lists(ours)?,
+        &property_lists(theirs)?,
+        &[],
+    );
+    let lists: Vec<Value> = lists
+        .into_iter()
+        .map(|(name, properties)| json!({ "name": name, "properties": properties }))
+        .collect();
+
+    let groups = merger.merge(
+        "groups",
+        Some(&lossless::to_value(&base.groups)?),
+        Some(&lossless::to_value(&ours.groups)?),
+        Some(&lossless::to_value(&theirs.groups)?),
+    );
+
+    Ok(MergeResult {
+        json: json!({
+            "header": header,
+            "items": items,
+            "properties": lists,
+            "groups": groups,
+        }),
+        conflicts: merger.conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, one_item, save};
+
+    fn merge_values(base: Value, ours: Value, theirs: Value) -> (Option<Value>, Vec<Conflict>) {
+        let mut merger = Merger {
+            options: MergeOptions::default(),
+            conflicts: vec![],
+        };
+        let merged = merger.merge("", Some(&base), Some(&ours), Some(&theirs));
+        (merged, merger.conflicts)
+    }
+
+    #[test]
+    fn test_merge_disjoint_edits() {
+        let (merged, conflicts) = merge_values(
+            json!({ "position": [0, 0, 0], "Color": 1, "Light": true }),
+            json!({ "position": [5, 0, 0], "Color": 1, "Light": true }),
+            json!({ "position": [0, 0, 0], "Color": 2 }),
+        );
+        assert!(conflicts.is_empty());
+        assert_eq!(merged, Some(json!({ "position": [5, 0, 0], "Color": 2 })));
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let (merged, conflicts) = merge_values(
+            json!({ "Color": 1 }),
+            json!({ "Color": 2 }),
+            json!({ "Color": 3 }),
+        );
+        assert_eq!(merged, Some(json!({ "Color": 2 })));
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                path: "Color".to_string(),
+                base: Some(json!(1)),
+                ours: Some(json!(2)),
+                theirs: Some(json!(3)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merge_saves() -> anyhow::Result<()> {
+        let base = one_item();
+        let mut ours = one_item();
+        ours.items[0].position.x += 100.0;
+        let mut theirs = one_item();
+        theirs.items[0].name = "Renamed".to_string();
+        let mut added = item("Chair");
+        added.guid = uuid::Uuid::from_u128(1);
+        theirs.items.push(added);
+
+        let result = merge(&base, &ours, &theirs, &MergeOptions::default())?;
+        assert!(result.conflicts.is_empty());
+        // the state is flattened back into the item
+        let merged = &result.json["items"][0];
+        assert!(merged.get("tinyrick").is_none());
+        assert!(merged.get("properties").is_some());
+
+        let mut expected = one_item();
+        expected.items[0].position.x += 100.0;
+        expected.items[0].name = "Renamed".to_string();
+        expected.items.push(theirs.items.pop().unwrap());
+        assert_eq!(result.into_save()?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_transform_whole() -> anyhow::Result<()> {
+        let base = save(vec![item("Chair")]);
+        let mut ours = save(vec![item("Chair")]);
+        ours.items[0].rotation.x = 0.5;
+        let mut theirs = save(vec![item("Chair")]);
+        theirs.items[0].rotation.w = 0.5;
+
+        let result = merge(&base, &ours, &theirs, &MergeOptions::default())?;
+        let paths: Vec<_> = result.conflicts.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["items[guid=00000000-0000-0000-0000-000000000000].rotation"]
+        );
+        let merged = result.into_save()?;
+        assert_eq!(merged.items[0].rotation, ours.items[0].rotation);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_duplicate_guids() -> anyhow::Result<()> {
+        let base = save(vec![item("Chair"), item("Table")]);
+        let ours = save(vec![item("Chair"), item("Table")]);
+        let mut theirs = save(vec![item("Chair"), item("Table")]);
+        theirs.items[1].position.z = 10.0;
+
+        let result = merge(&base, &ours, &theirs, &MergeOptions::default())?;
+        assert!(result.conflicts.is_empty());
+        let merged = result.into_save()?;
+        let names: Vec<_> = merged.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Chair", "Table"]);
+        assert_eq!(merged.items[1].position.z, 10.0);
+        Ok(())
+    }
+}
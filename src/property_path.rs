@@ -0,0 +1,563 @@
+//! Addressing single values inside item and property list properties.
+//!
+//! A path starts at an item, an item's actor, or a property list and then walks into the
+//! properties through their serde representation:
+//!
+//! ```text
+//! items[guid=5ec5775e-...].tinyrick.properties.Color.R
+//! items[3].tinyrick.actors[name=Light].properties.Brightness
+//! properties[name=CondoWeather_2729].properties.WeatherSwitchInterval
+//! ```
+//!
+//! Inside a property, the enum tags and `value` fields uesave wraps values in can be left
+//! out, so `Color.R` finds `Color.Struct.value.LinearColor.r`. Field names match case
+//! insensitively when there is no exact match.
+//!
+//! Values are read and written as lossless json, so NaN reads as `"#f32:7fc00000"`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use serde_json::Value;
+use uesave::Properties;
+
+use crate::lossless;
+use crate::suitebro::SuiteBro;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Field(String),
+    Index(usize),
+    Select { key: String, value: String },
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Field(name) => write!(f, ".{name}"),
+            Segment::Index(index) => write!(f, "[{index}]"),
+            Segment::Select { key, value } => write!(f, "[{key}={value}]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyPath {
+    pub segments: Vec<Segment>,
+}
+
+impl fmt::Display for PropertyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Field(name) if i == 0 => write!(f, "{name}")?,
+                segment => write!(f, "{segment}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PropertyPath {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut segments = vec![];
+        let mut rest = s;
+        while !rest.is_empty() {
+            if let Some(inner) = rest.strip_prefix('[') {
+                let end = inner
+                    .find(']')
+                    .ok_or_else(|| anyhow!("unclosed `[` in path `{s}`"))?;
+                let selector = &inner[..end];
+                segments.push(match selector.split_once('=') {
+                    Some((key, value)) => Segment::Select {
+                        key: key.trim().to_string(),
+                        value: value.trim().to_string(),
+                    },
+                    None => Segment::Index(
+                        selector
+                            .trim()
+                            .parse()
+                            .with_context(|| format!("invalid index `{selector}` in `{s}`"))?,
+                    ),
+                });
+                rest = &inner[end + 1..];
+            } else {
+                let field = rest.strip_prefix('.').unwrap_or(rest);
+                let end = field.find(['.', '[']).unwrap_or(field.len());
+                if end == 0 {
+                    bail!("empty field name in path `{s}`");
+                }
+                segments.push(Segment::Field(field[..end].to_string()));
+                rest = &field[end..];
+            }
+        }
+        if segments.is_empty() {
+            bail!("empty path");
+        }
+        Ok(PropertyPath { segments })
+    }
+}
+
+/// Where the `Properties` a path points into live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Item(usize),
+    Actor(usize, usize),
+    List(usize),
+}
+
+fn select<T>(
+    values: &[T],
+    segment: Option<&Segment>,
+    what: &str,
+    matches: impl Fn(&T, &str, &str) -> bool,
+) -> anyhow::Result<usize> {
+    match segment {
+        Some(Segment::Index(index)) if *index < values.len() => Ok(*index),
+        Some(Segment::Index(index)) => bail!("{what}[{index}] is out of range"),
+        Some(Segment::Select { key, value }) => {
+            let mut found = values
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| matches(*v, key.as_str(), value.as_str()));
+            match (found.next(), found.next()) {
+                (Some((index, _)), None) => Ok(index),
+                (None, _) => bail!("no {what} matches [{key}={value}]"),
+                (Some(_), Some(_)) => bail!("more than one {what} matches [{key}={value}]"),
+            }
+        }
+        _ => bail!("expected an index or selector after `{what}`"),
+    }
+}
+
+fn expect_field(segment: Option<&Segment>, name: &str) -> anyhow::Result<()> {
+    match segment {
+        Some(Segment::Field(field)) if field == name => Ok(()),
+        Some(other) => bail!("expected `.{name}`, found `{other}`"),
+        None => bail!("expected `.{name}`"),
+    }
+}
+
+/// Finds whose properties the path points into, returning the remaining segments.
+fn locate<'p>(save: &SuiteBro, path: &'p PropertyPath) -> anyhow::Result<(Target, &'p [Segment])> {
+    let segments = &path.segments;
+    match segments.first() {
+        Some(Segment::Field(root)) if root == "items" => {
+            let item = select(
+                &save.items,
+                segments.get(1),
+                "items",
+                |item, key, value| match key {
+                    "guid" => uuid::Uuid::parse_str(value).is_ok_and(|guid| guid == item.guid),
+                    "name" => item.name == value,
+                    _ => false,
+                },
+            )?;
+            expect_field(segments.get(2), "tinyrick")?;
+            let tinyrick = save.items[item]
+                .tinyrick
+                .as_ref()
+                .ok_or_else(|| anyhow!("items[{item}] has no tinyrick state"))?;
+            match segments.get(3) {
+                Some(Segment::Field(field)) if field == "actors" => {
+                    let actor = select(
+                        &tinyrick.actors,
+                        segments.get(4),
+                        "actors",
+                        |actor, key, value| key == "name" && actor.name == value,
+                    )?;
+                    expect_field(segments.get(5), "properties")?;
+                    Ok((Target::Actor(item, actor), &segments[6..]))
+                }
+                segment => {
+                    expect_field(segment, "properties")?;
+                    Ok((Target::Item(item), &segments[4..]))
+                }
+            }
+        }
+        Some(Segment::Field(root)) if root == "properties" => {
+            let list = select(
+                &save.properties,
+                segments.get(1),
+                "properties",
+                |list, key, value| key == "name" && list.name == value,
+            )?;
+            expect_field(segments.get(2), "properties")?;
+            Ok((Target::List(list), &segments[3..]))
+        }
+        _ => bail!("paths must start with `items` or `properties`"),
+    }
+}
+
+fn properties(save: &SuiteBro, target: Target) -> &Properties {
+    match target {
+        Target::Item(item) => &save.items[item].tinyrick.as_ref().unwrap().properties,
+        Target::Actor(item, actor) => {
+            &save.items[item].tinyrick.as_ref().unwrap().actors[actor].properties
+        }
+        Target::List(list) => &save.properties[list].properties,
+    }
+}
+
+fn properties_mut(save: &mut SuiteBro, target: Target) -> &mut Properties {
+    match target {
+        Target::Item(item) => &mut save.items[item].tinyrick.as_mut().unwrap().properties,
+        Target::Actor(item, actor) => {
+            &mut save.items[item].tinyrick.as_mut().unwrap().actors[actor].properties
+        }
+        Target::List(list) => &mut save.properties[list].properties,
+    }
+}
+
+/// The enum tags uesave wraps values in: property types, struct types and array kinds.
+const WRAPPER_TAGS: &[&str] = &[
+    "Int8",
+    "Int16",
+    "Int",
+    "Int64",
+    "UInt8",
+    "UInt16",
+    "UInt32",
+    "UInt64",
+    "Float",
+    "Double",
+    "Bool",
+    "Byte",
+    "Enum",
+    "Str",
+    "Name",
+    "Object",
+    "Text",
+    "FieldPath",
+    "SoftObject",
+    "Delegate",
+    "MulticastDelegate",
+    "MulticastInlineDelegate",
+    "MulticastSparseDelegate",
+    "Struct",
+    "Array",
+    "Set",
+    "Map",
+    "Base",
+    "Label",
+    "LinearColor",
+    "Color",
+    "Vector",
+    "Vector2D",
+    "Quat",
+    "Rotator",
+    "Guid",
+    "DateTime",
+    "Timespan",
+    "IntPoint",
+    "Box",
+];
+
+/// The value a wrapper object holds, if `value` is one.
+///
+/// uesave wraps values in enum tags (`{"Float": {...}}`) and structs with a `value` field.
+/// Other objects with a single field, like a struct with one property, are not wrappers.
+fn wrapped(value: &Value) -> Option<&str> {
+    let object = value.as_object()?;
+    if object.contains_key("value") {
+        return Some("value");
+    }
+    match object.len() {
+        1 => object
+            .keys()
+            .next()
+            .map(String::as_str)
+            .filter(|key| WRAPPER_TAGS.contains(key)),
+        _ => None,
+    }
+}
+
+/// `value` with every wrapper around it removed.
+pub fn unwrap_value(mut value: &Value) -> &Value {
+    while let Some(inner) = wrapped(value) {
+        value = &value[inner];
+    }
+    value
+}
+
+#[derive(Debug, Clone)]
+enum Key {
+    Field(String),
+    Index(usize),
+}
+
+fn child(value: &Value, key: &Key) -> Option<&Value> {
+    match key {
+        Key::Field(field) => value.get(field),
+        Key::Index(index) => value.get(index),
+    }
+}
+
+fn child_mut<'v>(value: &'v mut Value, key: &Key) -> Option<&'v mut Value> {
+    match key {
+        Key::Field(field) => value.get_mut(field),
+        Key::Index(index) => value.get_mut(index),
+    }
+}
+
+/// Resolves `segments` against `root` into the concrete keys to follow, unwrapping as needed.
+fn resolve(root: &Value, segments: &[Segment]) -> anyhow::Result<Vec<Key>> {
+    let mut keys = vec![];
+    let mut node = root;
+    for segment in segments {
+        loop {
+            let key = match (segment, node) {
+                (Segment::Field(field), Value::Object(object)) => object
+                    .keys()
+                    .find(|k| *k == field)
+                    .or_else(|| object.keys().find(|k| k.eq_ignore_ascii_case(field)))
+                    .map(|k| Key::Field(k.clone())),
+                (Segment::Index(index), Value::Array(array)) if *index < array.len() => {
+                    Some(Key::Index(*index))
+                }
+                (Segment::Select { .. }, _) => {
+                    bail!("selectors are not supported inside properties")
+                }
+                _ => None,
+            };
+            if let Some(key) = key {
+                node = child(node, &key).unwrap();
+                keys.push(key);
+                break;
+            }
+            match wrapped(node) {
+                Some(inner) => {
+                    let key = Key::Field(inner.to_string());
+                    node = child(node, &key).unwrap();
+                    keys.push(key);
+                }
+                None => bail!("`{segment}` not found"),
+            }
+        }
+    }
+    Ok(keys)
+}
+
+fn walk<'v>(mut node: &'v mut Value, keys: &[Key]) -> &'v mut Value {
+    for key in keys {
+        node = child_mut(node, key).unwrap();
+    }
+    node
+}
+
+fn same_shape(old: &Value, new: &Value) -> bool {
+    match (old, new) {
+        (Value::Null, Value::Null) | (Value::Bool(_), Value::Bool(_)) => true,
+        (Value::String(_), Value::String(_)) | (Value::Array(_), Value::Array(_)) => true,
+        (Value::Number(old), Value::Number(new)) => old.is_f64() || !new.is_f64(),
+        // NaN and infinity are tagged strings in lossless json
+        (Value::String(old), Value::Number(_)) => lossless::is_float(old),
+        (Value::Number(old), Value::String(new)) => old.is_f64() && lossless::is_float(new),
+        (Value::Object(old), Value::Object(new)) => {
+            old.len() == new.len() && old.keys().all(|k| new.contains_key(k))
+        }
+        _ => false,
+    }
+}
+
+/// Reads the value at `path`, with wrappers around it removed.
+pub fn get(save: &SuiteBro, path: &PropertyPath) -> anyhow::Result<Value> {
+    let (target, rest) = locate(save, path)?;
+    let root = lossless::to_value(properties(save, target))?;
+    let node = walk_ref(&root, &resolve(&root, rest)?);
+    Ok(unwrap_value(node).clone())
+}
+
+fn walk_ref<'v>(mut node: &'v Value, keys: &[Key]) -> &'v Value {
+    for key in keys {
+        node = child(node, key).unwrap();
+    }
+    node
+}
+
+/// Replaces the value at `path` with `value`, which must have the same type as the old one.
+pub fn set(save: &mut SuiteBro, path: &PropertyPath, value: Value) -> anyhow::Result<()> {
+    let (target, rest) = locate(save, path)?;
+    let Some(Segment::Field(name)) = rest.first() else {
+        bail!("`{path}` does not name a property");
+    };
+
+    let properties = properties_mut(save, target);
+    let property = properties
+        .get(name)
+        .ok_or_else(|| anyhow!("property `{name}` does not exist"))?;
+    let mut root = lossless::to_value(property)?;
+
+    let mut keys = resolve(&root, &rest[1..])?;
+    let mut node = walk_ref(&root, &keys);
+    while !same_shape(node, &value) {
+        let inner = wrapped(node).ok_or_else(|| {
+            anyhow!("cannot set `{path}` to {value}, the existing value is {node}")
+        })?;
+        keys.push(Key::Field(inner.to_string()));
+        node = &node[inner];
+    }
+    *walk(&mut root, &keys) = value;
+
+    let property = lossless::from_value(root)
+        .with_context(|| format!("new value does not fit the type of `{path}`"))?;
+    properties.insert(name.clone(), property);
+    Ok(())
+}
+
+/// Removes the property, struct field or array element at `path`.
+pub fn delete(save: &mut SuiteBro, path: &PropertyPath) -> anyhow::Result<()> {
+    let (target, rest) = locate(save, path)?;
+    let Some(Segment::Field(name)) = rest.first() else {
+        bail!("`{path}` does not name a property");
+    };
+
+    let properties = properties_mut(save, target);
+    if rest.len() == 1 {
+        return match properties.shift_remove(name) {
+            Some(_) => Ok(()),
+            None => Err(anyhow!("property `{name}` does not exist")),
+        };
+    }
+
+    let property = properties
+        .get(name)
+        .ok_or_else(|| anyhow!("property `{name}` does not exist"))?;
+    let mut root = lossless::to_value(property)?;
+    let mut keys = resolve(&root, &rest[1..])?;
+    let last = keys.pop().unwrap();
+    match (walk(&mut root, &keys), last) {
+        (Value::Object(object), Key::Field(field)) => {
+            *object = std::mem::take(object)
+                .into_iter()
+                .filter(|(key, _)| *key != field)
+                .collect();
+        }
+        (Value::Array(array), Key::Index(index)) => {
+            array.remove(index);
+        }
+        _ => unreachable!("keys are resolved against the same value"),
+    }
+
+    let property = lossless::from_value(root)
+        .with_context(|| format!("`{path}` cannot be removed from its property"))?;
+    properties.insert(name.clone(), property);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::one_item;
+    use serde_json::json;
+
+    /// an actor of the OneItem fixture
+    fn path(rest: &str) -> PropertyPath {
+        format!("items[0].tinyrick.actors[name=AudioDiffuserPlastic_C_1].properties.{rest}")
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let path: PropertyPath = "items[guid=5ec5775e].tinyrick.actors[0].properties.Color.R"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            path.segments,
+            vec![
+                Segment::Field("items".to_string()),
+                Segment::Select {
+                    key: "guid".to_string(),
+                    value: "5ec5775e".to_string(),
+                },
+                Segment::Field("tinyrick".to_string()),
+                Segment::Field("actors".to_string()),
+                Segment::Index(0),
+                Segment::Field("properties".to_string()),
+                Segment::Field("Color".to_string()),
+                Segment::Field("R".to_string()),
+            ]
+        );
+        assert_eq!(
+            path.to_string(),
+            "items[guid=5ec5775e].tinyrick.actors[0].properties.Color.R"
+        );
+        assert!("items[1".parse::<PropertyPath>().is_err());
+        assert!("items..x".parse::<PropertyPath>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_unwraps() {
+        let property = serde_json::json!({
+            "Struct": {
+                "id": null,
+                "value": { "LinearColor": { "r": 1.0, "g": 0.5, "b": 0.0, "a": 1.0 } },
+                "struct_type": "LinearColor",
+                "struct_id": "00000000-0000-0000-0000-000000000000"
+            }
+        });
+        let keys = resolve(&property, &[Segment::Field("R".to_string())]).unwrap();
+        assert_eq!(walk_ref(&property, &keys), &serde_json::json!(1.0));
+        assert!(resolve(&property, &[Segment::Field("x".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_unwrap_known_tags() {
+        let property = json!({ "Int": { "id": null, "value": 1 } });
+        assert_eq!(unwrap_value(&property), &json!(1));
+        // a struct with a single property keeps the property's name
+        let single = json!({ "OnlyField": { "Int": { "id": null, "value": 1 } } });
+        let value = json!({ "Struct": single });
+        assert_eq!(unwrap_value(&value), &single);
+    }
+
+    #[test]
+    fn test_set() -> anyhow::Result<()> {
+        let mut save = one_item();
+        set(&mut save, &path("Activated"), json!(false))?;
+        assert_eq!(get(&save, &path("Activated"))?, json!(false));
+        let mass = path("PhysicsSettings.MassMultiplier");
+        set(&mut save, &mass, json!(2.5))?;
+        assert_eq!(get(&save, &mass)?, json!(2.5));
+
+        // the wrong type is rejected and leaves the value alone
+        assert!(set(&mut save, &path("Activated"), json!("yes")).is_err());
+        let wrong = json!({ "x": 1.0 });
+        assert!(set(&mut save, &mass, wrong).is_err());
+        assert_eq!(get(&save, &path("Activated"))?, json!(false));
+        assert!(set(&mut save, &path("NotAProperty"), json!(1)).is_err());
+
+        // NaN reads as its bit pattern and can be set back to a number
+        set(&mut save, &mass, json!("#f32:7fc00000"))?;
+        assert_eq!(get(&save, &mass)?, json!("#f32:7fc00000"));
+        set(&mut save, &mass, json!(1.0))?;
+        assert_eq!(get(&save, &mass)?, json!(1.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete() -> anyhow::Result<()> {
+        let mut save = one_item();
+
+        let physics = path("PhysicsSettings");
+        let fields = get(&save, &physics)?.as_object().unwrap().len();
+        delete(&mut save, &path("PhysicsSettings.PhysicsRespawnDelay"))?;
+        assert!(get(&save, &path("PhysicsSettings.PhysicsRespawnDelay")).is_err());
+        let left = get(&save, &physics)?.as_object().unwrap().len();
+        assert_eq!(left, fields - 1);
+
+        let parts = get(&save, &path("Parts"))?.as_array().unwrap().len();
+        delete(&mut save, &path("Parts[0]"))?;
+        let left = get(&save, &path("Parts"))?.as_array().unwrap().len();
+        assert_eq!(left, parts - 1);
+
+        delete(&mut save, &path("Activated"))?;
+        assert!(get(&save, &path("Activated")).is_err());
+        assert!(delete(&mut save, &path("Activated")).is_err());
+        Ok(())
+    }
+}
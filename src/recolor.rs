@@ -0,0 +1,420 @@
+//! Finding and changing the colors stored in item properties.
+//!
+//! Colors are found through the serde representation of the properties, so any
+//! `LinearColor` or `Color` struct is picked up, including ones inside arrays and nested
+//! structs. `Color` components are bytes and are treated as `byte / 255`, without any
+//! gamma conversion.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uesave::Properties;
+
+use crate::lossless;
+use crate::suitebro::SuiteBro;
+
+/// Colors closer than this count as unchanged, hsv conversions aren't exact.
+const SAME_COLOR: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rgba {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    #[serde(default = "one")]
+    pub a: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+impl Rgba {
+    pub fn distance(&self, other: &Rgba) -> f32 {
+        let (dr, dg, db) = (self.r - other.r, self.g - other.g, self.b - other.b);
+        (dr * dr + dg * dg + db * db).sqrt()
+    }
+
+    /// Hue in degrees, saturation and value. Value can go above 1 for hdr colors.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / delta + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
+
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, a: f32) -> Rgba {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        Rgba {
+            r: r + m,
+            g: g + m,
+            b: b + m,
+            a,
+        }
+    }
+}
+
+impl fmt::Display for Rgba {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+    }
+}
+
+/// Parses `#rrggbb`, `#rrggbbaa` or comma separated floats `r,g,b[,a]`.
+impl FromStr for Rgba {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if let Some(hex) = s.strip_prefix('#') {
+            let byte = |i: usize| -> anyhow::Result<f32> {
+                let digits = hex
+                    .get(i..i + 2)
+                    .ok_or_else(|| anyhow!("invalid color `{s}`"))?;
+                Ok(u8::from_str_radix(digits, 16)? as f32 / 255.0)
+            };
+            return match hex.len() {
+                6 => Ok(Rgba {
+                    r: byte(0)?,
+                    g: byte(2)?,
+                    b: byte(4)?,
+                    a: 1.0,
+                }),
+                8 => Ok(Rgba {
+                    r: byte(0)?,
+                    g: byte(2)?,
+                    b: byte(4)?,
+                    a: byte(6)?,
+                }),
+                _ => bail!("invalid color `{s}`"),
+            };
+        }
+
+        let components = s
+            .split(',')
+            .map(|c| c.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()?;
+        match components[..] {
+            [r, g, b] => Ok(Rgba { r, g, b, a: 1.0 }),
+            [r, g, b, a] => Ok(Rgba { r, g, b, a }),
+            _ => bail!("invalid color `{s}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteEntry {
+    pub from: Rgba,
+    pub to: Rgba,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recolor {
+    /// replace colors within `tolerance` of an entry's `from` with its `to`, keeping alpha
+    Palette {
+        entries: Vec<PaletteEntry>,
+        tolerance: f32,
+    },
+    /// rotate the hue by `hue` degrees and scale saturation and value
+    HsvShift {
+        hue: f32,
+        saturation: f32,
+        value: f32,
+    },
+    /// blend towards `color` by `amount`, 0 keeps the original and 1 replaces it, amounts
+    /// outside of that are clamped
+    Tint { color: Rgba, amount: f32 },
+}
+
+impl Recolor {
+    /// The new color, or `None` if `color` is left alone.
+    pub fn apply(&self, color: Rgba) -> Option<Rgba> {
+        let new = match self {
+            Recolor::Palette { entries, tolerance } => {
+                let entry = entries
+                    .iter()
+                    .filter(|entry| entry.from.distance(&color) <= *tolerance)
+                    .min_by(|a, b| a.from.distance(&color).total_cmp(&b.from.distance(&color)))?;
+                Rgba {
+                    a: color.a,
+                    ..entry.to
+                }
+            }
+            Recolor::HsvShift {
+                hue,
+                saturation,
+                value,
+            } => {
+                let (h, s, v) = color.to_hsv();
+                Rgba::from_hsv(
+                    h + hue,
+                    (s * saturation).clamp(0.0, 1.0),
+                    (v * value).max(0.0),
+                    color.a,
+                )
+            }
+            Recolor::Tint {
+                color: tint,
+                amount,
+            } => {
+                let amount = amount.clamp(0.0, 1.0);
+                Rgba {
+                    r: color.r + (tint.r - color.r) * amount,
+                    g: color.g + (tint.g - color.g) * amount,
+                    b: color.b + (tint.b - color.b) * amount,
+                    a: color.a,
+                }
+            }
+        };
+        let changed = new.distance(&color) > SAME_COLOR || (new.a - color.a).abs() > SAME_COLOR;
+        changed.then_some(new)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ColorKind {
+    LinearColor,
+    Color,
+}
+
+fn read_component(object: &serde_json::Map<String, Value>, key: &str) -> Option<f64> {
+    object.get(key)?.as_f64()
+}
+
+fn read_color(value: &Value, kind: ColorKind) -> Option<Rgba> {
+    let object = value.as_object()?;
+    if object.len() != 4 {
+        return None;
+    }
+    let scale = match kind {
+        ColorKind::LinearColor => 1.0,
+        ColorKind::Color => 255.0,
+    };
+    Some(Rgba {
+        r: (read_component(object, "r")? / scale) as f32,
+        g: (read_component(object, "g")? / scale) as f32,
+        b: (read_component(object, "b")? / scale) as f32,
+        a: (read_component(object, "a")? / scale) as f32,
+    })
+}
+
+fn write_color(value: &mut Value, kind: ColorKind, color: Rgba) {
+    let component = |c: f32| match kind {
+        ColorKind::LinearColor => Value::from(c),
+        ColorKind::Color => Value::from((c * 255.0).round().clamp(0.0, 255.0) as u8),
+    };
+    *value = serde_json::json!({
+        "r": component(color.r),
+        "g": component(color.g),
+        "b": component(color.b),
+        "a": component(color.a),
+    });
+}
+
+/// Calls `f` with every color struct inside `value`.
+fn visit_colors(value: &mut Value, f: &mut impl FnMut(&mut Value, ColorKind, Rgba)) {
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                let kind = match key.as_str() {
+                    "LinearColor" => Some(ColorKind::LinearColor),
+                    "Color" => Some(ColorKind::Color),
+                    _ => None,
+                };
+                match kind.and_then(|kind| Some((kind, read_color(child, kind)?))) {
+                    Some((kind, color)) => f(child, kind, color),
+                    None => visit_colors(child, f),
+                }
+            }
+        }
+        Value::Array(array) => {
+            for child in array {
+                visit_colors(child, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn item_properties_mut(save: &mut SuiteBro) -> impl Iterator<Item = &mut Properties> {
+    save.items
+        .iter_mut()
+        .filter_map(|item| item.tinyrick.as_mut())
+        .flat_map(|tinyrick| {
+            std::iter::once(&mut tinyrick.properties).chain(
+                tinyrick
+                    .actors
+                    .iter_mut()
+                    .map(|actor| &mut actor.properties),
+            )
+        })
+}
+
+fn item_properties(save: &SuiteBro) -> impl Iterator<Item = &Properties> {
+    save.items
+        .iter()
+        .filter_map(|item| item.tinyrick.as_ref())
+        .flat_map(|tinyrick| {
+            std::iter::once(&tinyrick.properties)
+                .chain(tinyrick.actors.iter().map(|actor| &actor.properties))
+        })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColorUsage {
+    pub kind: ColorKind,
+    pub color: Rgba,
+    pub count: usize,
+}
+
+/// Every distinct color used by items and their actors, most used first.
+pub fn color_usage(save: &SuiteBro) -> anyhow::Result<Vec<ColorUsage>> {
+    let mut usage: IndexMap<(ColorKind, [u32; 4]), ColorUsage> = IndexMap::new();
+    for properties in item_properties(save) {
+        let mut value = lossless::to_value(properties)?;
+        visit_colors(&mut value, &mut |_, kind, color| {
+            let bits = [color.r, color.g, color.b, color.a].map(f32::to_bits);
+            usage
+                .entry((kind, bits))
+                .or_insert(ColorUsage {
+                    kind,
+                    color,
+                    count: 0,
+                })
+                .count += 1;
+        });
+    }
+    let mut usage: Vec<_> = usage.into_values().collect();
+    usage.sort_by(|a, b| b.count.cmp(&a.count));
+    Ok(usage)
+}
+
+/// Applies `recolor` to every color in items and their actors, returning how many changed.
+pub fn recolor(save: &mut SuiteBro, recolor: &Recolor) -> anyhow::Result<usize> {
+    let mut changed = 0;
+    for properties in item_properties_mut(save) {
+        let mut value = lossless::to_value(&*properties)?;
+        let before = changed;
+        visit_colors(&mut value, &mut |color_value, kind, color| {
+            if let Some(new) = recolor.apply(color) {
+                let old = color_value.clone();
+                write_color(color_value, kind, new);
+                // a small change can round to the same bytes
+                if *color_value != old {
+                    changed += 1;
+                }
+            }
+        });
+        if changed != before {
+            *properties = lossless::from_value(value)?;
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hsv_round_trip() {
+        let color = Rgba {
+            r: 0.2,
+            g: 0.6,
+            b: 0.4,
+            a: 1.0,
+        };
+        let (h, s, v) = color.to_hsv();
+        let back = Rgba::from_hsv(h, s, v, color.a);
+        assert!(color.distance(&back) < 1e-5);
+    }
+
+    #[test]
+    fn test_palette() {
+        let red = "#ff0000".parse::<Rgba>().unwrap();
+        let blue = "0,0,1".parse::<Rgba>().unwrap();
+        let recolor = Recolor::Palette {
+            entries: vec![PaletteEntry {
+                from: red,
+                to: blue,
+            }],
+            tolerance: 0.01,
+        };
+        let almost_red = Rgba {
+            g: 0.005,
+            a: 0.5,
+            ..red
+        };
+        assert_eq!(recolor.apply(almost_red), Some(Rgba { a: 0.5, ..blue }));
+        assert_eq!(recolor.apply(blue), None);
+    }
+
+    #[test]
+    fn test_visit_colors() {
+        let mut value = serde_json::json!({
+            "Tint": { "Struct": { "value": { "LinearColor": { "r": 1.0, "g": 0.0, "b": 0.0, "a": 1.0 } } } },
+            "Glow": { "Struct": { "value": { "Color": { "r": 255, "g": 0, "b": 0, "a": 255 } } } },
+        });
+        let mut found = vec![];
+        visit_colors(&mut value, &mut |v, kind, color| {
+            found.push((kind, color));
+            write_color(v, kind, Rgba { r: 0.0, ..color });
+        });
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].1, found[1].1);
+        assert_eq!(value["Glow"]["Struct"]["value"]["Color"]["r"], 0);
+    }
+
+    #[test]
+    fn test_unchanged_within_tolerance() {
+        let color = Rgba {
+            r: 0.2,
+            g: 0.6,
+            b: 0.4,
+            a: 1.0,
+        };
+        let full_turn = Recolor::HsvShift {
+            hue: 360.0,
+            saturation: 1.0,
+            value: 1.0,
+        };
+        assert_eq!(full_turn.apply(color), None);
+    }
+
+    #[test]
+    fn test_tint_amount_clamped() {
+        let red = "#ff0000".parse::<Rgba>().unwrap();
+        let blue = "#0000ff".parse::<Rgba>().unwrap();
+        let tint = |amount| Recolor::Tint {
+            color: blue,
+            amount,
+        };
+        assert_eq!(tint(5.0).apply(red), Some(blue));
+        assert_eq!(tint(-1.0).apply(red), None);
+        assert_eq!(tint(0.5).apply(red).map(|c| c.b), Some(0.5));
+    }
+}
@@ -0,0 +1,176 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::suitebro::SuiteBro;
+
+/// What to do with the `TinyRick` state of a replaced item.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateAction {
+    /// keep the state as is
+    #[default]
+    Keep,
+    /// remove the state entirely
+    Drop,
+    /// keep only the listed properties, renamed from the old to the new name
+    Remap(IndexMap<String, String>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassReplacement {
+    /// class name the items are changed to
+    pub name: String,
+    /// workshop id of the new class, 0 if it isn't a workshop item, items keep their own
+    /// when missing
+    #[serde(default)]
+    pub steam_item_id: Option<u64>,
+    #[serde(default)]
+    pub state: StateAction,
+}
+
+/// Replacements keyed by the class name they apply to.
+///
+/// ```json
+/// {
+///   "ChairWood": { "name": "ChairMetal" },
+///   "LampOld": { "name": "LampNew", "state": { "remap": { "Color": "LightColor" } } },
+///   "WorkshopSofa": { "name": "WorkshopCouch", "steam_item_id": 123456, "state": "drop" }
+/// }
+/// ```
+pub type ClassMapping = IndexMap<String, ClassReplacement>;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Replaced {
+    pub index: usize,
+    pub guid: uuid::Uuid,
+    pub from: String,
+    pub to: String,
+    pub state: StateAction,
+}
+
+/// Changes the class of every item named in `mapping`, keeping its transform.
+///
+/// Returns what was (or, on a dry run, would be) replaced.
+pub fn replace_classes(
+    save: &mut SuiteBro,
+    mapping: &ClassMapping,
+    dry_run: bool,
+) -> Vec<Replaced> {
+    let mut replaced = vec![];
+    for (index, item) in save.items.iter_mut().enumerate() {
+        let Some(replacement) = mapping.get(&item.name) else {
+            continue;
+        };
+
+        // items without state have nothing to keep or remap
+        let state = match item.tinyrick {
+            Some(_) => replacement.state.clone(),
+            None => StateAction::Keep,
+        };
+
+        replaced.push(Replaced {
+            index,
+            guid: item.guid,
+            from: item.name.clone(),
+            to: replacement.name.clone(),
+            state: state.clone(),
+        });
+
+        if dry_run {
+            continue;
+        }
+
+        item.name = replacement.name.clone();
+        if let Some(steam_item_id) = replacement.steam_item_id {
+            item.steam_item_id = steam_item_id;
+        }
+        match state {
+            StateAction::Keep => {}
+            StateAction::Drop => item.tinyrick = None,
+            StateAction::Remap(renames) => {
+                if let Some(tinyrick) = &mut item.tinyrick {
+                    tinyrick.properties = std::mem::take(&mut tinyrick.properties)
+                        .into_iter()
+                        .filter_map(|(name, property)| {
+                            renames.get(&name).map(|new| (new.clone(), property))
+                        })
+                        .collect();
+                }
+            }
+        }
+    }
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, one_item, save};
+
+    fn mapping(json: &str) -> ClassMapping {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_replace_by_class() {
+        let mut save = save(vec![item("Chair"), item("Table"), item("Chair")]);
+        save.items[0].steam_item_id = 7;
+        save.items[2].position.x = 10.0;
+        let mapping = mapping(r#"{ "Chair": { "name": "Stool" } }"#);
+
+        let dry = replace_classes(&mut save, &mapping, true);
+        assert_eq!(dry.len(), 2);
+        assert_eq!(save.items[0].name, "Chair");
+
+        let replaced = replace_classes(&mut save, &mapping, false);
+        assert_eq!(replaced, dry);
+        let indices: Vec<_> = replaced.iter().map(|r| r.index).collect();
+        assert_eq!(indices, [0, 2]);
+        let names: Vec<_> = save.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Stool", "Table", "Stool"]);
+        // no id in the mapping keeps the item's own
+        assert_eq!(save.items[0].steam_item_id, 7);
+        assert_eq!(save.items[2].position.x, 10.0);
+
+        let mapping = mapping(r#"{ "Stool": { "name": "Sofa", "steam_item_id": 0 } }"#);
+        replace_classes(&mut save, &mapping, false);
+        assert_eq!(save.items[0].steam_item_id, 0);
+    }
+
+    #[test]
+    fn test_missing_mapping() {
+        let mut save = save(vec![item("Chair")]);
+        let mapping = mapping(r#"{ "Lamp": { "name": "LampNew" } }"#);
+        assert!(replace_classes(&mut save, &mapping, false).is_empty());
+        assert_eq!(save.items[0].name, "Chair");
+    }
+
+    #[test]
+    fn test_state() {
+        let mut save = one_item();
+        let name = save.items[0].name.clone();
+        let property = save.items[0]
+            .tinyrick
+            .as_ref()
+            .and_then(|tinyrick| tinyrick.properties.keys().next().cloned())
+            .expect("the fixture has state");
+
+        let remap = format!(
+            r#"{{ "{name}": {{ "name": "Remapped", "state": {{ "remap": {{ "{property}": "Renamed" }} }} }} }}"#
+        );
+        replace_classes(&mut save, &mapping(&remap), false);
+        let tinyrick = save.items[0].tinyrick.as_ref().unwrap();
+        let names: Vec<_> = tinyrick.properties.keys().collect();
+        assert_eq!(names, ["Renamed"]);
+
+        let drop = r#"{ "Remapped": { "name": "Dropped", "state": "drop" } }"#;
+        let replaced = replace_classes(&mut save, &mapping(drop), false);
+        assert_eq!(replaced[0].state, StateAction::Drop);
+        assert!(save.items[0].tinyrick.is_none());
+
+        // nothing left to drop
+        let drop = r#"{ "Dropped": { "name": "Again", "state": "drop" } }"#;
+        let replaced = replace_classes(&mut save, &mapping(drop), false);
+        assert_eq!(replaced[0].state, StateAction::Keep);
+    }
+}
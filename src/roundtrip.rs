@@ -0,0 +1,234 @@
+//! Checking that a save is written back byte for byte, and finding where it isn't.
+
+use std::fmt;
+use std::io::Cursor;
+use std::ops::Range;
+
+use serde::Serialize;
+use uesave::{write_properties_none_terminated, write_string, Properties, Readable, Writable};
+
+use crate::byte_size::StaticByteSize;
+use crate::diff::join_field;
+use crate::lossless;
+use crate::size::{measure, property_sizes, write_bytes};
+use crate::suitebro::{get_tower_types, SuiteBro};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// read and write the binary format
+    Binary,
+    /// go through `serde_json` in between
+    Json,
+    /// go through the lossless json encoding in between
+    LosslessJson,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Mismatch {
+    pub offset: usize,
+    /// missing past the end of the file
+    pub expected: Option<u8>,
+    pub found: Option<u8>,
+    /// the part of the save the offset belongs to
+    pub location: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |b: Option<u8>| match b {
+            Some(b) => format!("{b:#04x}"),
+            None => "end of file".to_string(),
+        };
+        write!(
+            f,
+            "first difference at offset {:#x} ({}) in {}: expected {}, found {}",
+            self.offset,
+            self.offset,
+            self.location,
+            byte(self.expected),
+            byte(self.found)
+        )
+    }
+}
+
+/// Byte ranges of the parts of a save, in file order.
+#[derive(Debug, Default)]
+struct Layout {
+    spans: Vec<(Range<usize>, String)>,
+    offset: usize,
+}
+
+impl Layout {
+    fn push(&mut self, len: usize, name: String) {
+        self.spans.push((self.offset..self.offset + len, name));
+        self.offset += len;
+    }
+
+    fn string(&mut self, s: &str, name: String) -> uesave::TResult<()> {
+        self.push(measure(|ctx| write_string(ctx, s))?, name);
+        Ok(())
+    }
+
+    fn properties(&mut self, properties: &Properties, path: &str) -> uesave::TResult<()> {
+        for (name, len) in property_sizes(properties)? {
+            self.push(len, join_field(path, &name));
+        }
+        let none = measure(|ctx| write_properties_none_terminated(ctx, &Properties::default()))?;
+        self.push(none, format!("{path} terminator"));
+        Ok(())
+    }
+
+    /// Measures every part of the save.
+    fn of(save: &SuiteBro) -> uesave::TResult<Layout> {
+        let mut layout = Layout::default();
+        layout.push(crate::suitebro::Header::BYTE_SIZE, "header".into());
+        layout.push(4, "item count".into());
+        for (index, item) in save.items.iter().enumerate() {
+            let path = format!("items[{index}]");
+            layout.string(&item.name, format!("{path}.name"))?;
+            layout.push(uuid::Uuid::BYTE_SIZE, format!("{path}.guid"));
+            layout.push(4, format!("{path} state flag"));
+            layout.push(8, format!("{path}.steam_item_id"));
+            if let Some(tinyrick) = &item.tinyrick {
+                layout.push(4, format!("{path}.tinyrick size"));
+                // magic and versions
+                layout.push(16, format!("{path}.tinyrick header"));
+                layout.properties(&tinyrick.properties, &format!("{path}.tinyrick.properties"))?;
+                layout.push(8, format!("{path}.tinyrick actor count"));
+                for (i, actor) in tinyrick.actors.iter().enumerate() {
+                    let len = measure(|ctx| actor.write(ctx))?;
+                    layout.push(len, format!("{path}.tinyrick.actors[{i}]"));
+                }
+            }
+            layout.push(uesave::Quat::BYTE_SIZE, format!("{path}.rotation"));
+            layout.push(uesave::Vector::BYTE_SIZE, format!("{path}.position"));
+            layout.push(uesave::Vector::BYTE_SIZE, format!("{path}.scale"));
+        }
+
+        layout.push(4, "property list count".into());
+        for (index, list) in save.properties.iter().enumerate() {
+            let path = format!("properties[{index}]");
+            layout.string(&list.name, format!("{path}.name"))?;
+            layout.push(4, format!("{path} size"));
+            layout.properties(&list.properties, &format!("{path}.properties"))?;
+            layout.push(4, format!("{path} separator"));
+        }
+
+        layout.push(4, "unknown count".into());
+        layout.push(4, "group count".into());
+        for index in 0..save.groups.len() {
+            layout.push(
+                crate::suitebro::GroupInfo::BYTE_SIZE,
+                format!("groups[{index}]"),
+            );
+        }
+        Ok(layout)
+    }
+
+    fn locate(&self, offset: usize) -> String {
+        self.spans
+            .iter()
+            .find(|(range, _)| range.contains(&offset))
+            .map(|(_, name)| name.clone())
+            .unwrap_or_else(|| "the end of the save".to_string())
+    }
+}
+
+/// The first byte where `found` differs from `expected`, located in `save`, which is
+/// what `expected` holds.
+pub fn first_difference(
+    save: &SuiteBro,
+    expected: &[u8],
+    found: &[u8],
+) -> uesave::TResult<Option<Mismatch>> {
+    let offset = expected
+        .iter()
+        .zip(found)
+        .position(|(a, b)| a != b)
+        .or_else(|| (expected.len() != found.len()).then_some(expected.len().min(found.len())));
+    let Some(offset) = offset else {
+        return Ok(None);
+    };
+    Ok(Some(Mismatch {
+        offset,
+        expected: expected.get(offset).copied(),
+        found: found.get(offset).copied(),
+        location: Layout::of(save)?.locate(offset),
+    }))
+}
+
+/// Reads `input`, writes it back in memory the way `mode` says and compares the bytes.
+pub fn round_trip(input: &[u8], mode: Mode) -> anyhow::Result<Option<Mismatch>> {
+    let save = uesave::Context::run_with_types(
+        &get_tower_types(),
+        &mut Cursor::new(input),
+        SuiteBro::read,
+    )?;
+    let output = match mode {
+        Mode::Binary => write_bytes(|ctx| save.write(ctx))?,
+        Mode::Json => {
+            let read: SuiteBro = serde_json::from_slice(&serde_json::to_vec(&save)?)?;
+            write_bytes(|ctx| read.write(ctx))?
+        }
+        Mode::LosslessJson => {
+            let mut json = vec![];
+            lossless::to_writer_pretty(&mut json, &save)?;
+            let read: SuiteBro = lossless::from_reader(&json[..])?;
+            write_bytes(|ctx| read.write(ctx))?
+        }
+    };
+    Ok(first_difference(&save, input, &output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{one_item, ONE_ITEM};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_fixtures_round_trip() -> anyhow::Result<()> {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        for entry in fs::read_dir(assets)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let input = fs::read(&path)?;
+            for mode in [Mode::Binary, Mode::Json, Mode::LosslessJson] {
+                if let Some(mismatch) = round_trip(&input, mode)? {
+                    panic!("{} through {mode:?}: {mismatch}", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_locate() -> anyhow::Result<()> {
+        let save = one_item();
+        let layout = Layout::of(&save)?;
+        assert_eq!(layout.offset, ONE_ITEM.len());
+        assert_eq!(layout.locate(0), "header");
+        assert_eq!(layout.locate(ONE_ITEM.len()), "the end of the save");
+
+        let scale = layout
+            .spans
+            .iter()
+            .find(|(_, name)| name == "items[0].scale")
+            .unwrap()
+            .0
+            .start;
+        let mut changed = ONE_ITEM.to_vec();
+        changed[scale + 1] ^= 0xff;
+        let mismatch = first_difference(&save, ONE_ITEM, &changed)?.unwrap();
+        assert_eq!(mismatch.offset, scale + 1);
+        assert_eq!(mismatch.location, "items[0].scale");
+
+        let mismatch = first_difference(&save, ONE_ITEM, &ONE_ITEM[..10])?.unwrap();
+        assert_eq!(mismatch.offset, 10);
+        assert_eq!(mismatch.found, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+//! Repairing item transforms the game can't handle.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde::Serialize;
+use uesave::{Quat, Vector};
+
+use crate::suitebro::item::Item;
+use crate::suitebro::SuiteBro;
+use crate::transform::{quat_array, quat_length, vector_array};
+
+/// What to do with an item that has a NaN or infinite component.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// zero position, identity rotation and unit scale for the broken parts
+    #[default]
+    Reset,
+    Drop,
+}
+
+impl FromStr for NonFinite {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "reset" => Ok(NonFinite::Reset),
+            "drop" => Ok(NonFinite::Drop),
+            _ => bail!("unknown policy `{s}`, expected reset or drop"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SanitizeOptions {
+    pub non_finite: NonFinite,
+    /// how far a rotation's length may be from 1 before it is normalized
+    pub rotation_tolerance: f32,
+    /// bounds on the size of each scale component, negative (mirrored) scales keep their sign
+    pub min_scale: f32,
+    pub max_scale: f32,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        SanitizeOptions {
+            non_finite: NonFinite::Reset,
+            rotation_tolerance: 1e-4,
+            min_scale: 0.01,
+            max_scale: 1000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Position,
+    Rotation,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Reset,
+    Normalized,
+    Clamped,
+    Dropped,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Change {
+    /// index of the item before any were dropped
+    pub index: usize,
+    pub guid: uuid::Uuid,
+    pub name: String,
+    pub field: Field,
+    pub action: Action,
+    pub from: Vec<f32>,
+    /// missing when the item was dropped
+    pub to: Option<Vec<f32>>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} {}: {:?} {:?} {:?}",
+            self.index, self.name, self.guid, self.action, self.field, self.from
+        )?;
+        match &self.to {
+            Some(to) => write!(f, " -> {to:?}"),
+            None => Ok(()),
+        }
+    }
+}
+
+fn finite(values: &[f32]) -> bool {
+    values.iter().all(|c| c.is_finite())
+}
+
+/// The repairs an item needs, without applying them.
+fn item_changes(
+    item: &Item,
+    options: &SanitizeOptions,
+) -> Vec<(Field, Action, Vec<f32>, Vec<f32>)> {
+    let mut changes = vec![];
+
+    let position = vector_array(&item.position);
+    if !finite(&position) {
+        let to = position.map(|c| if c.is_finite() { c } else { 0.0 });
+        changes.push((
+            Field::Position,
+            Action::Reset,
+            position.to_vec(),
+            to.to_vec(),
+        ));
+    }
+
+    let rotation = quat_array(&item.rotation);
+    let length = quat_length(&item.rotation);
+    if !finite(&rotation) || length == 0.0 || !length.is_finite() {
+        let to = [0.0, 0.0, 0.0, 1.0];
+        changes.push((
+            Field::Rotation,
+            Action::Reset,
+            rotation.to_vec(),
+            to.to_vec(),
+        ));
+    } else if (length - 1.0).abs() > options.rotation_tolerance {
+        let to = rotation.map(|c| c / length);
+        changes.push((
+            Field::Rotation,
+            Action::Normalized,
+            rotation.to_vec(),
+            to.to_vec(),
+        ));
+    }
+
+    let scale = vector_array(&item.scale);
+    if !finite(&scale) {
+        let to = scale.map(|c| if c.is_finite() { c } else { 1.0 });
+        changes.push((Field::Scale, Action::Reset, scale.to_vec(), to.to_vec()));
+    }
+    let reset = changes.last().filter(|(field, ..)| *field == Field::Scale);
+    let current = reset.map_or(scale.to_vec(), |(.., to)| to.clone());
+    let clamped: Vec<f32> = current
+        .iter()
+        .map(|c| c.signum() * c.abs().clamp(options.min_scale, options.max_scale))
+        .collect();
+    if clamped != current {
+        changes.push((Field::Scale, Action::Clamped, current, clamped));
+    }
+    changes
+}
+
+fn set_field(item: &mut Item, field: Field, to: &[f32]) {
+    match field {
+        Field::Position => {
+            item.position = Vector {
+                x: to[0],
+                y: to[1],
+                z: to[2],
+            }
+        }
+        Field::Rotation => {
+            item.rotation = Quat {
+                x: to[0],
+                y: to[1],
+                z: to[2],
+                w: to[3],
+            }
+        }
+        Field::Scale => {
+            item.scale = Vector {
+                x: to[0],
+                y: to[1],
+                z: to[2],
+            }
+        }
+    }
+}
+
+/// Normalizes rotations, repairs NaN and infinite components and clamps scales.
+///
+/// Returns every change in item order.
+pub fn sanitize(save: &mut SuiteBro, options: &SanitizeOptions) -> Vec<Change> {
+    let mut all = vec![];
+    let mut drop = vec![false; save.items.len()];
+    for (index, item) in save.items.iter_mut().enumerate() {
+        let changes = item_changes(item, options);
+        let broken = changes
+            .iter()
+            .find(|(_, action, ..)| *action == Action::Reset);
+        if let (Some((field, _, from, _)), NonFinite::Drop) = (broken, options.non_finite) {
+            drop[index] = true;
+            all.push(Change {
+                index,
+                guid: item.guid,
+                name: item.name.clone(),
+                field: *field,
+                action: Action::Dropped,
+                from: from.clone(),
+                to: None,
+            });
+            continue;
+        }
+        for (field, action, from, to) in changes {
+            set_field(item, field, &to);
+            all.push(Change {
+                index,
+                guid: item.guid,
+                name: item.name.clone(),
+                field,
+                action,
+                from,
+                to: Some(to),
+            });
+        }
+    }
+
+    let mut index = 0;
+    save.items.retain(|_| {
+        let keep = !drop[index];
+        index += 1;
+        keep
+    });
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::item;
+
+    fn save() -> SuiteBro {
+        let mut items = vec![item("Fine"), item("Broken"), item("Skewed")];
+        items[1].position.y = f32::NAN;
+        items[1].scale.z = f32::INFINITY;
+        items[2].rotation.w = 2.0;
+        items[2].scale.x = -0.001;
+        crate::suitebro::tests::save(items)
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut save = save();
+        let changes = sanitize(&mut save, &SanitizeOptions::default());
+        let found: Vec<_> = changes
+            .iter()
+            .map(|c| (c.index, c.field, c.action))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, Field::Position, Action::Reset),
+                (1, Field::Scale, Action::Reset),
+                (2, Field::Rotation, Action::Normalized),
+                (2, Field::Scale, Action::Clamped),
+            ]
+        );
+        assert_eq!(save.items.len(), 3);
+        assert_eq!(vector_array(&save.items[1].position), [0.0; 3]);
+        assert_eq!(vector_array(&save.items[1].scale), [1.0, 1.0, 1.0]);
+        assert_eq!(quat_array(&save.items[2].rotation), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(vector_array(&save.items[2].scale), [-0.01, 1.0, 1.0]);
+
+        // already sane
+        assert!(sanitize(&mut save, &SanitizeOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_mirrored_scale() {
+        let mut mirrored = item("Mirrored");
+        mirrored.scale.x = -1.0;
+        mirrored.scale.y = -5000.0;
+        mirrored.scale.z = 0.0;
+        let mut save = crate::suitebro::tests::save(vec![mirrored]);
+
+        let changes = sanitize(&mut save, &SanitizeOptions::default());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].action, Action::Clamped);
+        assert_eq!(vector_array(&save.items[0].scale), [-1.0, -1000.0, 0.01]);
+    }
+
+    #[test]
+    fn test_drop() {
+        let mut save = save();
+        let options = SanitizeOptions {
+            non_finite: NonFinite::Drop,
+            ..Default::default()
+        };
+        let changes = sanitize(&mut save, &options);
+        assert_eq!(changes[0].action, Action::Dropped);
+        assert_eq!(changes[0].index, 1);
+        let names: Vec<_> = save.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Fine", "Skewed"]);
+    }
+}
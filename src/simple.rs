@@ -0,0 +1,423 @@
+//! A json dialect with plain values in place of uesave's tagged properties.
+//!
+//! `{"Struct": {"id": null, "value": {"LinearColor": {"r": 1.0, ...}}, ...}}` becomes
+//! `{"r": 1.0, ...}`. Everything that was taken away is kept in a sidecar [`SimpleSchema`],
+//! so [`restore`] puts back exactly what [`simplify`] removed. Values can be edited
+//! freely, and array elements added or removed, as long as their shape stays the same.
+//! Items are matched to their schema by guid, so items can be removed or reordered too.
+//!
+//! A simple document has `"style": "simple"` and the file name of its schema next to
+//! the usual fields.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::suitebro::SuiteBro;
+
+pub const STYLE_FIELD: &str = "style";
+pub const SCHEMA_FIELD: &str = "schema";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Style {
+    /// uesave's own layout
+    #[default]
+    Tagged,
+    Simple,
+}
+
+impl FromStr for Style {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "tagged" | "json" => Ok(Style::Tagged),
+            "simple" => Ok(Style::Simple),
+            _ => bail!("unknown style `{s}`, expected tagged or simple"),
+        }
+    }
+}
+
+/// What [`simplify`] took away from a value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Schema {
+    /// written as it is
+    #[default]
+    Plain,
+    /// `{tag: {...fields, "value": value}}`
+    Wrapped {
+        tag: String,
+        fields: Map<String, Value>,
+        value: Box<Schema>,
+    },
+    /// `{tag: value}`
+    Tagged { tag: String, value: Box<Schema> },
+    /// property name to property
+    Properties(IndexMap<String, Schema>),
+    /// fields that aren't plain
+    Object(IndexMap<String, Schema>),
+    /// per element, elements past the end use the last one
+    Array(Vec<Schema>),
+    /// the same for every element
+    Each(Box<Schema>),
+}
+
+impl Schema {
+    fn is_plain(&self) -> bool {
+        *self == Schema::Plain
+    }
+}
+
+/// Sidecar schema of a simple document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SimpleSchema {
+    /// per item guid, later items reusing a guid get `#2`, `#3` and so on appended
+    pub items: IndexMap<String, Schema>,
+    /// per property list name
+    pub properties: IndexMap<String, Schema>,
+}
+
+/// Tags of single field objects that are taken away, uesave names these after types.
+fn is_tag(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+fn simplify_value(value: Value) -> (Value, Schema) {
+    match value {
+        Value::Object(object) if object.len() == 1 => {
+            let (tag, inner) = object.into_iter().next().unwrap();
+            match inner {
+                // a property, `{"Int": {"id": null, "value": 1}}`
+                Value::Object(mut fields) if fields.contains_key("value") => {
+                    let (plain, schema) = simplify_value(fields.remove("value").unwrap());
+                    let schema = Schema::Wrapped {
+                        tag,
+                        fields,
+                        value: Box::new(schema),
+                    };
+                    (plain, schema)
+                }
+                // nested properties, `{"Struct": {"Name": {...}}}`
+                Value::Object(properties) if tag == "Struct" => {
+                    let (plain, schema) = simplify_properties(properties);
+                    let schema = Schema::Tagged {
+                        tag,
+                        value: Box::new(schema),
+                    };
+                    (plain, schema)
+                }
+                // a struct or array type, `{"LinearColor": {"r": 1.0, ...}}`
+                inner if is_tag(&tag) => {
+                    let (plain, schema) = simplify_value(inner);
+                    let schema = Schema::Tagged {
+                        tag,
+                        value: Box::new(schema),
+                    };
+                    (plain, schema)
+                }
+                inner => {
+                    let (plain, schema) = simplify_value(inner);
+                    let mut object = Map::new();
+                    object.insert(tag.clone(), plain);
+                    let schema = match schema.is_plain() {
+                        true => Schema::Plain,
+                        false => Schema::Object(IndexMap::from([(tag, schema)])),
+                    };
+                    (Value::Object(object), schema)
+                }
+            }
+        }
+        Value::Object(object) => {
+            let mut plain = Map::new();
+            let mut schemas = IndexMap::new();
+            for (key, value) in object {
+                let (value, schema) = simplify_value(value);
+                if !schema.is_plain() {
+                    schemas.insert(key.clone(), schema);
+                }
+                plain.insert(key, value);
+            }
+            let schema = match schemas.is_empty() {
+                true => Schema::Plain,
+                false => Schema::Object(schemas),
+            };
+            (Value::Object(plain), schema)
+        }
+        Value::Array(array) => {
+            let (plain, schemas): (Vec<_>, Vec<_>) = array.into_iter().map(simplify_value).unzip();
+            let schema = match schemas.first() {
+                None => Schema::Plain,
+                Some(first) if schemas.iter().all(|s| s == first) => match first.is_plain() {
+                    true => Schema::Plain,
+                    false => Schema::Each(Box::new(first.clone())),
+                },
+                Some(_) => Schema::Array(schemas),
+            };
+            (Value::Array(plain), schema)
+        }
+        leaf => (leaf, Schema::Plain),
+    }
+}
+
+fn simplify_properties(properties: Map<String, Value>) -> (Value, Schema) {
+    let mut plain = Map::new();
+    let mut schemas = IndexMap::new();
+    for (name, property) in properties {
+        let (value, schema) = simplify_value(property);
+        plain.insert(name.clone(), value);
+        schemas.insert(name, schema);
+    }
+    (Value::Object(plain), Schema::Properties(schemas))
+}
+
+fn restore_value(plain: Value, schema: &Schema, path: &str) -> anyhow::Result<Value> {
+    Ok(match schema {
+        Schema::Plain => plain,
+        Schema::Wrapped { tag, fields, value } => {
+            let mut fields = fields.clone();
+            fields.insert("value".to_string(), restore_value(plain, value, path)?);
+            let mut object = Map::new();
+            object.insert(tag.clone(), Value::Object(fields));
+            Value::Object(object)
+        }
+        Schema::Tagged { tag, value } => {
+            let mut object = Map::new();
+            object.insert(tag.clone(), restore_value(plain, value, path)?);
+            Value::Object(object)
+        }
+        Schema::Properties(schemas) | Schema::Object(schemas) => {
+            let Value::Object(object) = plain else {
+                bail!("{path}: expected an object, found {plain}");
+            };
+            let mut restored = Map::new();
+            for (key, value) in object {
+                let schema = match (schemas.get(&key), schema) {
+                    (Some(schema), _) => schema,
+                    (None, Schema::Properties(_)) => {
+                        bail!("{path}.{key}: new properties need uesave's tagged layout")
+                    }
+                    (None, _) => &Schema::Plain,
+                };
+                let value = restore_value(value, schema, &format!("{path}.{key}"))?;
+                restored.insert(key, value);
+            }
+            Value::Object(restored)
+        }
+        Schema::Array(schemas) => {
+            let Value::Array(array) = plain else {
+                bail!("{path}: expected an array, found {plain}");
+            };
+            let last = schemas.last().unwrap_or(&Schema::Plain);
+            array
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let schema = schemas.get(i).unwrap_or(last);
+                    restore_value(value, schema, &format!("{path}[{i}]"))
+                })
+                .collect::<anyhow::Result<_>>()?
+        }
+        Schema::Each(schema) => {
+            let Value::Array(array) = plain else {
+                bail!("{path}: expected an array, found {plain}");
+            };
+            array
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| restore_value(value, schema, &format!("{path}[{i}]")))
+                .collect::<anyhow::Result<_>>()?
+        }
+    })
+}
+
+/// Keys items by guid, numbering repeated guids.
+fn item_keys(items: &[Value]) -> Vec<String> {
+    let mut seen: IndexMap<String, usize> = IndexMap::new();
+    items
+        .iter()
+        .map(|item| {
+            let guid = item["guid"].as_str().unwrap_or_default().to_string();
+            let count = seen.entry(guid.clone()).or_insert(0);
+            *count += 1;
+            match count {
+                1 => guid,
+                n => format!("{guid}#{n}"),
+            }
+        })
+        .collect()
+}
+
+/// Simplifies the properties of an item or actor, in place.
+fn simplify_object(object: &mut Map<String, Value>) -> Schema {
+    let mut schemas = IndexMap::new();
+    if let Some(Value::Object(properties)) = object.remove("properties") {
+        let (plain, schema) = simplify_properties(properties);
+        object.insert("properties".to_string(), plain);
+        schemas.insert("properties".to_string(), schema);
+    }
+    if let Some(Value::Array(actors)) = object.get_mut("actors") {
+        let actors = actors
+            .iter_mut()
+            .filter_map(Value::as_object_mut)
+            .map(simplify_object)
+            .collect();
+        schemas.insert("actors".to_string(), Schema::Array(actors));
+    }
+    Schema::Object(schemas)
+}
+
+/// Turns the json of a save into the simple dialect, `schema_file` is recorded in it.
+pub fn simplify_json(mut json: Value, schema_file: &str) -> anyhow::Result<(Value, SimpleSchema)> {
+    let mut schema = SimpleSchema::default();
+
+    if let Some(Value::Array(items)) = json.get_mut("items") {
+        for (key, item) in item_keys(items).into_iter().zip(items.iter_mut()) {
+            if let Some(item) = item.as_object_mut() {
+                if item.contains_key("properties") {
+                    schema.items.insert(key, simplify_object(item));
+                }
+            }
+        }
+    }
+    if let Some(Value::Array(lists)) = json.get_mut("properties") {
+        for list in lists.iter_mut().filter_map(Value::as_object_mut) {
+            let name = list["name"].as_str().unwrap_or_default().to_string();
+            schema.properties.insert(name, simplify_object(list));
+        }
+    }
+
+    let object = json
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("a save must be a json object"))?;
+    object.insert(STYLE_FIELD.to_string(), "simple".into());
+    object.insert(SCHEMA_FIELD.to_string(), schema_file.into());
+    Ok((json, schema))
+}
+
+pub fn simplify(save: &SuiteBro, schema_file: &str) -> anyhow::Result<(Value, SimpleSchema)> {
+    simplify_json(crate::lossless::to_value(save)?, schema_file)
+}
+
+/// Whether `json` is a simple document, returning the file name of its schema.
+pub fn schema_file(json: &Value) -> Option<&str> {
+    match json.get(STYLE_FIELD)?.as_str()? {
+        "simple" => json.get(SCHEMA_FIELD)?.as_str(),
+        _ => None,
+    }
+}
+
+/// Turns a simple document back into the json of a save.
+pub fn restore_json(mut json: Value, schema: &SimpleSchema) -> anyhow::Result<Value> {
+    let object = json
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("a save must be a json object"))?;
+    object.remove(SCHEMA_FIELD);
+    object.remove(STYLE_FIELD);
+
+    if let Some(Value::Array(items)) = json.get_mut("items") {
+        for (key, item) in item_keys(items).into_iter().zip(items.iter_mut()) {
+            let path = format!("items[guid={key}]");
+            match schema.items.get(&key) {
+                Some(item_schema) => {
+                    *item = restore_value(std::mem::take(item), item_schema, &path)?;
+                }
+                None if item.get("properties").is_some() => {
+                    bail!("{path}: no schema for this item, new items need uesave's tagged layout")
+                }
+                None => {}
+            }
+        }
+    }
+    if let Some(Value::Array(lists)) = json.get_mut("properties") {
+        for list in lists.iter_mut() {
+            let name = list["name"].as_str().unwrap_or_default().to_string();
+            let path = format!("properties[name={name}]");
+            let list_schema = schema
+                .properties
+                .get(&name)
+                .with_context(|| format!("{path}: no schema for this property list"))?;
+            *list = restore_value(std::mem::take(list), list_schema, &path)?;
+        }
+    }
+    Ok(json)
+}
+
+pub fn restore(json: Value, schema: &SimpleSchema) -> anyhow::Result<SuiteBro> {
+    Ok(crate::lossless::from_value(restore_json(json, schema)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::one_item;
+    use serde_json::json;
+
+    #[test]
+    fn test_simplify_value() -> anyhow::Result<()> {
+        let property = |r: f64| {
+            json!({ "Struct": {
+                "id": null,
+                "value": { "LinearColor": { "r": r, "g": 0.5, "b": 0.0, "a": 1.0 } },
+                "struct_type": "LinearColor",
+                "struct_id": "00000000-0000-0000-0000-000000000000",
+            } })
+        };
+        let properties = json!({
+            "Tint": property(1.0),
+            "Count": { "Int": { "id": null, "value": 3 } },
+            "Nested": { "Struct": {
+                "id": null,
+                "value": { "Struct": { "Only": { "Bool": { "id": null, "value": true } } } },
+                "struct_type": { "Struct": "Nested" },
+                "struct_id": "00000000-0000-0000-0000-000000000000",
+            } },
+        });
+
+        let Value::Object(map) = properties.clone() else {
+            unreachable!()
+        };
+        let (plain, schema) = simplify_properties(map);
+        assert_eq!(
+            plain,
+            json!({
+                "Tint": { "r": 1.0, "g": 0.5, "b": 0.0, "a": 1.0 },
+                "Count": 3,
+                "Nested": { "Only": true },
+            })
+        );
+        assert_eq!(restore_value(plain.clone(), &schema, "")?, properties);
+
+        let mut edited = plain;
+        edited["Count"] = json!(4);
+        let restored = restore_value(edited, &schema, "")?;
+        assert_eq!(restored["Count"]["Int"]["value"], 4);
+
+        let colors = json!([property(0.0), property(1.0)]);
+        let (plain, schema) = simplify_value(colors);
+        assert!(matches!(schema, Schema::Each(_)));
+        let mut edited = plain;
+        edited.as_array_mut().unwrap().push(edited[0].clone());
+        let restored = restore_value(edited, &schema, "")?;
+        assert_eq!(restored[2], property(0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simplify_save() -> anyhow::Result<()> {
+        let save = one_item();
+
+        let (simple, schema) = simplify(&save, "save.schema.json")?;
+        assert_eq!(schema_file(&simple), Some("save.schema.json"));
+        let tagged = serde_json::to_string(&save)?;
+        assert!(serde_json::to_string(&simple)?.len() < tagged.len());
+
+        // the schema survives being written to its own file
+        let schema: SimpleSchema = serde_json::from_str(&serde_json::to_string(&schema)?)?;
+        assert_eq!(restore(simple, &schema)?, save);
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+//! Where the bytes of a save go.
+//!
+//! Items and property lists are measured by writing them on their own, so the numbers
+//! match the file exactly. A tinyrick property is measured as the bytes it adds to an
+//! otherwise empty property list.
+
+use std::fmt;
+use std::io::Cursor;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use uesave::{write_properties_none_terminated, Properties, Writable};
+
+use crate::byte_size::ByteSize;
+use crate::suitebro::{get_tower_types, SuiteBro};
+
+/// The bytes `write` produces on its own.
+pub(crate) fn write_bytes(
+    write: impl FnOnce(&mut uesave::Context<Cursor<Vec<u8>>>) -> uesave::TResult<()>,
+) -> uesave::TResult<Vec<u8>> {
+    let mut cursor = Cursor::new(vec![]);
+    uesave::Context::run_with_types(&get_tower_types(), &mut cursor, write)?;
+    Ok(cursor.into_inner())
+}
+
+pub(crate) fn measure(
+    write: impl FnOnce(&mut uesave::Context<Cursor<Vec<u8>>>) -> uesave::TResult<()>,
+) -> uesave::TResult<usize> {
+    Ok(write_bytes(write)?.len())
+}
+
+/// Bytes each property adds to a property list, in order.
+pub(crate) fn property_sizes(properties: &Properties) -> uesave::TResult<Vec<(String, usize)>> {
+    let empty = measure(|ctx| write_properties_none_terminated(ctx, &Properties::default()))?;
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let mut single = Properties::default();
+            single.insert(name.clone(), property.clone());
+            let size = measure(|ctx| write_properties_none_terminated(ctx, &single))?;
+            Ok((name.clone(), size - empty))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SizeEntry {
+    pub name: String,
+    pub count: usize,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ItemSize {
+    pub index: usize,
+    pub guid: uuid::Uuid,
+    pub name: String,
+    pub bytes: usize,
+    /// bytes taken by the tinyrick, including its length
+    pub state_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SizeReport {
+    pub total: usize,
+    pub header: usize,
+    pub groups: usize,
+    /// largest first
+    pub items: Vec<ItemSize>,
+    /// bytes per item class, largest first
+    pub classes: Vec<SizeEntry>,
+    /// bytes per tinyrick property name over every item, largest first
+    pub properties: Vec<SizeEntry>,
+    /// largest first
+    pub property_lists: Vec<SizeEntry>,
+}
+
+impl SizeReport {
+    /// Keeps only the `n` largest entries of every listing.
+    pub fn top(mut self, n: usize) -> Self {
+        self.items.truncate(n);
+        self.classes.truncate(n);
+        self.properties.truncate(n);
+        self.property_lists.truncate(n);
+        self
+    }
+}
+
+fn add(entries: &mut IndexMap<String, SizeEntry>, name: &str, bytes: usize) {
+    let entry = entries
+        .entry(name.to_string())
+        .or_insert_with(|| SizeEntry {
+            name: name.to_string(),
+            count: 0,
+            bytes: 0,
+        });
+    entry.count += 1;
+    entry.bytes += bytes;
+}
+
+fn largest_first(entries: IndexMap<String, SizeEntry>) -> Vec<SizeEntry> {
+    let mut entries: Vec<_> = entries.into_values().collect();
+    entries.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    entries
+}
+
+/// Measures every part of the save.
+pub fn size_report(save: &SuiteBro) -> uesave::TResult<SizeReport> {
+    let total = measure(|ctx| save.write(ctx))?;
+
+    let mut items = vec![];
+    let mut classes = IndexMap::new();
+    let mut properties = IndexMap::new();
+    for (index, item) in save.items.iter().enumerate() {
+        let bytes = measure(|ctx| item.write(ctx))?;
+        let state_bytes = match &item.tinyrick {
+            Some(tinyrick) => measure(|ctx| tinyrick.write(ctx))? + 4,
+            None => 0,
+        };
+        add(&mut classes, &item.name, bytes);
+        if let Some(tinyrick) = &item.tinyrick {
+            for (name, bytes) in property_sizes(&tinyrick.properties)? {
+                add(&mut properties, &name, bytes);
+            }
+        }
+        items.push(ItemSize {
+            index,
+            guid: item.guid,
+            name: item.name.clone(),
+            bytes,
+            state_bytes,
+        });
+    }
+    items.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+
+    let mut property_lists = IndexMap::new();
+    for list in &save.properties {
+        add(
+            &mut property_lists,
+            &list.name,
+            measure(|ctx| list.write(ctx))?,
+        );
+    }
+
+    Ok(SizeReport {
+        total,
+        header: save.header.byte_size(),
+        groups: save.groups.byte_size(),
+        items,
+        classes: largest_first(classes),
+        properties: largest_first(properties),
+        property_lists: largest_first(property_lists),
+    })
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |bytes: usize| bytes as f64 * 100.0 / self.total.max(1) as f64;
+
+        writeln!(f, "total: {} bytes", self.total)?;
+        writeln!(f, "header: {} bytes", self.header)?;
+        writeln!(f, "groups: {} bytes", self.groups)?;
+
+        writeln!(f, "items:")?;
+        for item in &self.items {
+            writeln!(
+                f,
+                "  {:>9} {:>5.1}% {} {} (#{}, {} bytes of state)",
+                item.bytes,
+                percent(item.bytes),
+                item.name,
+                item.guid,
+                item.index,
+                item.state_bytes
+            )?;
+        }
+        for (title, entries) in [
+            ("classes", &self.classes),
+            ("tinyrick properties", &self.properties),
+            ("property lists", &self.property_lists),
+        ] {
+            writeln!(f, "{title}:")?;
+            for entry in entries {
+                writeln!(
+                    f,
+                    "  {:>9} {:>5.1}% {} ({}x)",
+                    entry.bytes,
+                    percent(entry.bytes),
+                    entry.name,
+                    entry.count
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{one_item, ONE_ITEM};
+
+    #[test]
+    fn test_size_report() -> anyhow::Result<()> {
+        let save = one_item();
+        let report = size_report(&save)?;
+        assert_eq!(report.total, ONE_ITEM.len());
+        assert_eq!(report.header, 16);
+        let items: usize = report.items.iter().map(|item| item.bytes).sum();
+        let lists: usize = report.property_lists.iter().map(|list| list.bytes).sum();
+        // item, property list, unknown and group counts
+        assert_eq!(
+            report.header + 4 * 4 + items + lists + report.groups,
+            report.total
+        );
+        for item in &report.items {
+            assert!(item.state_bytes < item.bytes);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::suitebro::SuiteBro;
+use crate::transform::vector_array;
+use crate::workshop::WorkshopId;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Bounds {
+    pub fn size(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| self.max[i] - self.min[i])
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| (self.min[i] + self.max[i]) / 2.0)
+    }
+
+    pub fn contains(&self, point: [f32; 3], margin: f32) -> bool {
+        (0..3).all(|i| point[i] >= self.min[i] - margin && point[i] <= self.max[i] + margin)
+    }
+}
+
+/// Bounding box of every item position, skipping positions that aren't finite.
+pub fn item_bounds(save: &SuiteBro) -> Option<Bounds> {
+    save.items
+        .iter()
+        .map(|item| vector_array(&item.position))
+        .filter(|p| p.iter().all(|c| c.is_finite()))
+        .fold(None, |bounds, p| {
+            Some(match bounds {
+                None => Bounds { min: p, max: p },
+                Some(Bounds { min, max }) => Bounds {
+                    min: [0, 1, 2].map(|i| min[i].min(p[i])),
+                    max: [0, 1, 2].map(|i| max[i].max(p[i])),
+                },
+            })
+        })
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Stats {
+    pub format_version: u32,
+    pub unreal_version: u32,
+    pub items: usize,
+    pub items_with_state: usize,
+    pub items_without_state: usize,
+    pub actors: usize,
+    pub groups: usize,
+    /// item count per class name, most used first
+    pub classes: IndexMap<String, usize>,
+    /// item count per workshop id, most used first
+    pub workshop_items: IndexMap<WorkshopId, usize>,
+    pub bounds: Option<Bounds>,
+    pub property_lists: Vec<String>,
+}
+
+fn sorted_by_count<K>(mut counts: IndexMap<K, usize>) -> IndexMap<K, usize> {
+    counts.sort_by(|_, a, _, b| b.cmp(a));
+    counts
+}
+
+pub fn stats(save: &SuiteBro) -> Stats {
+    let mut classes = IndexMap::new();
+    let mut workshop_items = IndexMap::new();
+    let mut items_with_state = 0;
+    let mut actors = 0;
+    for item in &save.items {
+        *classes.entry(item.name.clone()).or_insert(0) += 1;
+        if let Some(id) = item.workshop_id() {
+            *workshop_items.entry(id).or_insert(0) += 1;
+        }
+        if let Some(tinyrick) = &item.tinyrick {
+            items_with_state += 1;
+            actors += tinyrick.actors.len();
+        }
+    }
+
+    Stats {
+        format_version: save.header.format_version,
+        unreal_version: save.header.unreal_version,
+        items: save.items.len(),
+        items_with_state,
+        items_without_state: save.items.len() - items_with_state,
+        actors,
+        groups: save.groups.len(),
+        classes: sorted_by_count(classes),
+        workshop_items: sorted_by_count(workshop_items),
+        bounds: item_bounds(save),
+        property_lists: save
+            .properties
+            .iter()
+            .map(|list| list.name.clone())
+            .collect(),
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "format version: {}", self.format_version)?;
+        writeln!(f, "unreal version: {}", self.unreal_version)?;
+        writeln!(
+            f,
+            "items: {} ({} with state, {} without)",
+            self.items, self.items_with_state, self.items_without_state
+        )?;
+        writeln!(f, "actors: {}", self.actors)?;
+        writeln!(f, "groups: {}", self.groups)?;
+        if let Some(bounds) = &self.bounds {
+            writeln!(f, "bounds: {:?} to {:?}", bounds.min, bounds.max)?;
+        }
+
+        writeln!(f, "classes:")?;
+        for (name, count) in &self.classes {
+            writeln!(f, "  {count:>6} {name}")?;
+        }
+        writeln!(f, "workshop items:")?;
+        for (id, count) in &self.workshop_items {
+            writeln!(f, "  {count:>6} {id}")?;
+        }
+        writeln!(f, "property lists:")?;
+        for name in &self.property_lists {
+            writeln!(f, "  {name}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, one_item};
+
+    #[test]
+    fn test_stats() {
+        let mut save = one_item();
+        let position = vector_array(&save.items[0].position);
+        let name = save.items[0].name.clone();
+
+        let report = stats(&save);
+        assert_eq!(report.format_version, 1);
+        assert_eq!(report.unreal_version, 517);
+        assert_eq!(report.items, 1);
+        assert_eq!(report.items_with_state, 1);
+        assert_eq!(report.items_without_state, 0);
+        assert_eq!(report.classes.get(&name), Some(&1));
+        let bounds = report.bounds.unwrap();
+        assert_eq!((bounds.min, bounds.max), (position, position));
+        assert_eq!(bounds.size(), [0.0; 3]);
+
+        let mut far = item("Chair");
+        far.position.x = position[0] + 100.0;
+        far.position.y = position[1];
+        far.position.z = position[2] - 50.0;
+        far.steam_item_id = 42;
+        let mut broken = item("Chair");
+        broken.position.y = f32::NAN;
+        save.items.push(far);
+        save.items.push(broken);
+
+        let report = stats(&save);
+        assert_eq!(report.items, 3);
+        assert_eq!(report.items_without_state, 2);
+        assert_eq!(report.classes.first(), Some((&"Chair".to_string(), &2)));
+        let bounds = report.bounds.unwrap();
+        assert_eq!(bounds.min, [position[0], position[1], position[2] - 50.0]);
+        assert_eq!(bounds.max, [position[0] + 100.0, position[1], position[2]]);
+        assert!(bounds.contains(position, 0.0));
+        let workshop_id = WorkshopId::new(42).unwrap();
+        assert_eq!(report.workshop_items.get(&workshop_id), Some(&1));
+    }
+}
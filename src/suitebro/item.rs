@@ -2,6 +2,8 @@ use std::io::{Cursor, Read, Seek, Write};
 
 use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 use serde::{Deserialize, Serialize};
+
+use crate::workshop::WorkshopId;
 use uesave::{
     read_array, read_properties_until_none, write_properties_none_terminated, write_string,
     Properties, Quat, Readable, Vector, Writable,
@@ -16,11 +18,19 @@ pub struct Item {
     pub steam_item_id: u64,
     #[serde(flatten)]
     pub tinyrick: Option<TinyRick>,
+    /// read as a quat or as pitch, yaw and roll, see `euler`
+    #[serde(deserialize_with = "crate::euler::deserialize_rotation")]
     pub rotation: Quat,
     pub position: Vector,
     pub scale: Vector,
 }
 
+impl Item {
+    pub fn workshop_id(&self) -> Option<WorkshopId> {
+        WorkshopId::new(self.steam_item_id)
+    }
+}
+
 impl<R: Read + Seek> Readable<R> for Item {
     fn read(reader: &mut uesave::Context<R>) -> uesave::TResult<Self> {
         let name = uesave::read_string(reader)?;        
@@ -132,8 +142,8 @@ impl<R: Write + Seek> Writable<R> for TinyRick {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct ActorInfo {
-	name: String,
-	properties: Properties
+    pub name: String,
+    pub properties: Properties,
 }
 
 impl<R: Read + Seek> Readable<R> for ActorInfo {
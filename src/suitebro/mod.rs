@@ -70,6 +70,9 @@ pub struct Header {
     pub unreal_version: u32,
 }
 
+// magic, format_version, unreal_version
+crate::impl_static_size_struct!(Header { u64, u32, u32 });
+
 impl<R: Read + Seek> Readable<R> for Header {
     fn read(reader: &mut uesave::Context<R>) -> uesave::TResult<Self> {
         let magic = reader.read_u64::<LE>()?;
@@ -144,6 +147,8 @@ pub struct GroupInfo {
     group_id: u32,
 }
 
+crate::impl_static_size_struct!(GroupInfo { u32, u32 });
+
 impl<R: Read + Seek> Readable<R> for GroupInfo {
     fn read(reader: &mut uesave::Context<R>) -> uesave::TResult<Self> {
         let item_count = reader.read_u32::<LE>()?;
@@ -278,6 +283,57 @@ pub(crate) mod tests {
         ]
     );
 
+    pub(crate) const ONE_ITEM: &[u8] = include_bytes!("../../assets/OneItem");
+
+    /// [`ONE_ITEM`], read.
+    pub(crate) fn one_item() -> SuiteBro {
+        uesave::Context::run_with_types(
+            &get_tower_types(),
+            &mut Cursor::new(ONE_ITEM),
+            SuiteBro::read,
+        )
+        .expect("assets/OneItem reads")
+    }
+
+    /// An item at the origin, unrotated and unscaled.
+    pub(crate) fn item(name: &str) -> Item {
+        Item {
+            name: name.to_string(),
+            guid: uuid::Uuid::nil(),
+            steam_item_id: 0,
+            tinyrick: None,
+            rotation: uesave::Quat {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+            position: uesave::Vector {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            scale: uesave::Vector {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        }
+    }
+
+    /// A save holding only `items`.
+    pub(crate) fn save(items: Vec<Item>) -> SuiteBro {
+        SuiteBro {
+            header: Header {
+                format_version: 1,
+                unreal_version: 517,
+            },
+            items,
+            properties: vec![],
+            groups: vec![],
+        }
+    }
+
     // test_rw!(
     //     test_suitebro,
     //     SuiteBro,
@@ -0,0 +1,117 @@
+//! A stable line oriented rendering of a save, for `git diff` textconv.
+//!
+//! Every leaf value is written on its own line as `path = value`, with items addressed
+//! by guid so a moved item shows up as a few changed lines rather than a shifted block.
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use crate::diff::join_field;
+use crate::suitebro::SuiteBro;
+
+fn write_leaves(out: &mut impl Write, path: &str, value: &Value) -> std::io::Result<()> {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                write_leaves(out, &join_field(path, key), value)?;
+            }
+            Ok(())
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (index, value) in array.iter().enumerate() {
+                write_leaves(out, &format!("{path}[{index}]"), value)?;
+            }
+            Ok(())
+        }
+        leaf => writeln!(out, "{path} = {leaf}"),
+    }
+}
+
+pub fn render(save: &SuiteBro, out: &mut impl Write) -> anyhow::Result<()> {
+    write_leaves(out, "header", &serde_json::to_value(&save.header)?)?;
+
+    for item in &save.items {
+        let path = format!("items[guid={}]", item.guid);
+        writeln!(out, "{path}.name = {}", Value::from(item.name.as_str()))?;
+        writeln!(out, "{path}.steam_item_id = {}", item.steam_item_id)?;
+        let p = &item.position;
+        writeln!(out, "{path}.position = ({}, {}, {})", p.x, p.y, p.z)?;
+        let r = &item.rotation;
+        writeln!(
+            out,
+            "{path}.rotation = ({}, {}, {}, {})",
+            r.x, r.y, r.z, r.w
+        )?;
+        let s = &item.scale;
+        writeln!(out, "{path}.scale = ({}, {}, {})", s.x, s.y, s.z)?;
+        if let Some(tinyrick) = &item.tinyrick {
+            write_leaves(
+                out,
+                &format!("{path}.tinyrick"),
+                &serde_json::to_value(tinyrick)?,
+            )?;
+        }
+    }
+
+    for list in &save.properties {
+        write_leaves(
+            out,
+            &format!("properties[name={}].properties", list.name),
+            &serde_json::to_value(&list.properties)?,
+        )?;
+    }
+
+    write_leaves(out, "groups", &serde_json::to_value(&save.groups)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, one_item, save};
+
+    fn lines(save: &SuiteBro) -> Vec<String> {
+        let mut out = vec![];
+        render(save, &mut out).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_render() {
+        let mut chair = item("Chair");
+        chair.guid = uuid::Uuid::from_u128(1);
+        chair.position.x = 1.5;
+        chair.steam_item_id = 7;
+        assert_eq!(
+            lines(&save(vec![chair])),
+            [
+                "header.format_version = 1",
+                "header.unreal_version = 517",
+                "items[guid=00000000-0000-0000-0000-000000000001].name = \"Chair\"",
+                "items[guid=00000000-0000-0000-0000-000000000001].steam_item_id = 7",
+                "items[guid=00000000-0000-0000-0000-000000000001].position = (1.5, 0, 0)",
+                "items[guid=00000000-0000-0000-0000-000000000001].rotation = (0, 0, 0, 1)",
+                "items[guid=00000000-0000-0000-0000-000000000001].scale = (1, 1, 1)",
+                "groups = []",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_state() {
+        let save = one_item();
+        let rendered = lines(&save);
+        let item = format!("items[guid={}]", save.items[0].guid);
+        let state: Vec<_> = rendered
+            .iter()
+            .filter(|line| line.starts_with(&format!("{item}.tinyrick.properties.")))
+            .collect();
+        assert!(!state.is_empty());
+        assert_eq!(rendered, lines(&one_item()));
+    }
+}
@@ -0,0 +1,137 @@
+use uesave::{Quat, Vector};
+
+use crate::suitebro::item::Item;
+
+/// How far apart two transforms can be and still count as the same.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// distance between positions, in unreal units
+    pub position: f32,
+    /// angle between rotations, in degrees
+    pub rotation: f32,
+    /// largest difference between any scale component
+    pub scale: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            position: 0.1,
+            rotation: 0.1,
+            scale: 0.001,
+        }
+    }
+}
+
+impl Tolerance {
+    pub fn same_position(&self, a: &Vector, b: &Vector) -> bool {
+        vector_distance(a, b) <= self.position
+    }
+
+    pub fn same_rotation(&self, a: &Quat, b: &Quat) -> bool {
+        quat_angle_degrees(a, b) <= self.rotation
+    }
+
+    pub fn same_scale(&self, a: &Vector, b: &Vector) -> bool {
+        vector_max_delta(a, b) <= self.scale
+    }
+
+    pub fn same_transform(&self, a: &Item, b: &Item) -> bool {
+        self.same_position(&a.position, &b.position)
+            && self.same_rotation(&a.rotation, &b.rotation)
+            && self.same_scale(&a.scale, &b.scale)
+    }
+}
+
+pub fn vector_distance(a: &Vector, b: &Vector) -> f32 {
+    let (dx, dy, dz) = (a.x - b.x, a.y - b.y, a.z - b.z);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// Largest per-component difference between two vectors, infinite if either isn't finite.
+pub fn vector_max_delta(a: &Vector, b: &Vector) -> f32 {
+    let finite = |v: &Vector| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+    if !finite(a) || !finite(b) {
+        return f32::INFINITY;
+    }
+    (a.x - b.x)
+        .abs()
+        .max((a.y - b.y).abs())
+        .max((a.z - b.z).abs())
+}
+
+pub fn quat_dot(a: &Quat, b: &Quat) -> f32 {
+    a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w
+}
+
+pub fn quat_length(q: &Quat) -> f32 {
+    quat_dot(q, q).sqrt()
+}
+
+/// Angle in degrees of the rotation taking `a` to `b`.
+///
+/// `q` and `-q` describe the same rotation, so this is 0 for both. It is infinite if
+/// either isn't finite, so a broken rotation is never within a tolerance.
+pub fn quat_angle_degrees(a: &Quat, b: &Quat) -> f32 {
+    let finite =
+        |q: &Quat| q.x.is_finite() && q.y.is_finite() && q.z.is_finite() && q.w.is_finite();
+    if !finite(a) || !finite(b) {
+        return f32::INFINITY;
+    }
+    let len = quat_length(a) * quat_length(b);
+    if len == 0.0 {
+        return 180.0;
+    }
+    let dot = (quat_dot(a, b) / len).abs().min(1.0);
+    (2.0 * dot.acos()).to_degrees()
+}
+
+pub fn vector_array(v: &Vector) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+pub fn quat_array(q: &Quat) -> [f32; 4] {
+    [q.x, q.y, q.z, q.w]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quat(x: f32, y: f32, z: f32, w: f32) -> Quat {
+        Quat { x, y, z, w }
+    }
+
+    #[test]
+    fn test_quat_angle() {
+        let identity = quat(0.0, 0.0, 0.0, 1.0);
+        assert_eq!(
+            quat_angle_degrees(&identity, &quat(0.0, 0.0, 0.0, -1.0)),
+            0.0
+        );
+        let turned = quat(0.0, 0.0, 0.5f32.sqrt(), 0.5f32.sqrt());
+        assert!((quat_angle_degrees(&identity, &turned) - 90.0).abs() < 0.01);
+
+        let broken = quat(0.0, 0.0, 0.0, f32::NAN);
+        assert_eq!(quat_angle_degrees(&identity, &broken), f32::INFINITY);
+        assert_eq!(quat_angle_degrees(&broken, &broken), f32::INFINITY);
+        assert!(!Tolerance::default().same_rotation(&broken, &identity));
+    }
+
+    #[test]
+    fn test_vector_max_delta() {
+        let one = Vector {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let nan = Vector {
+            x: 1.0,
+            y: f32::NAN,
+            z: 1.0,
+        };
+        assert_eq!(vector_max_delta(&one, &one), 0.0);
+        assert_eq!(vector_max_delta(&one, &nan), f32::INFINITY);
+        assert!(!Tolerance::default().same_scale(&one, &nan));
+    }
+}
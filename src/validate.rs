@@ -0,0 +1,516 @@
+//! Reading hand-edited json with errors that say where the problem is.
+//!
+//! serde_json only knows the line and column of a bad value, and nothing at all once a
+//! save has gone through `serde_json::Value`. Here every part of a save is read on its
+//! own, so a bad value is reported as `items[12].tinyrick.properties.Color.Struct...`
+//! along with what was expected. Semantic checks then catch saves that read fine but
+//! shouldn't be written.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use uesave::{Quat, Vector};
+
+use crate::lossless::LosslessDeserializer;
+use crate::suitebro::item::TinyRick;
+use crate::suitebro::{GroupInfo, Header, PropertyList, SuiteBro};
+
+/// Groups are only read back from saves with this format version or later.
+const GROUPS_FORMAT_VERSION: u32 = 517;
+
+/// Package versions of Unreal Engine 4 and 5.
+const UNREAL_VERSIONS: [RangeInclusive<u64>; 2] = [214..=522, 1000..=1012];
+
+const TINYRICK_FIELDS: &[&str] = &["format_version", "unreal_version", "properties", "actors"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problems(pub Vec<Problem>);
+
+impl fmt::Display for Problems {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, problem) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Problems {}
+
+/// The parts of a save that aren't items.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Outline {
+    header: Header,
+    items: Vec<IgnoredAny>,
+    properties: Vec<PropertyList>,
+    groups: Vec<GroupInfo>,
+}
+
+/// An item without the flattened `TinyRick`.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct ItemFields {
+    name: String,
+    guid: uuid::Uuid,
+    steam_item_id: u64,
+    #[serde(deserialize_with = "crate::euler::deserialize_rotation")]
+    rotation: Quat,
+    position: Vector,
+    scale: Vector,
+}
+
+fn join(prefix: &str, path: &str) -> String {
+    match (prefix, path) {
+        (prefix, ".") => prefix.to_string(),
+        ("", path) => path.to_string(),
+        (prefix, path) if path.starts_with('[') => format!("{prefix}{path}"),
+        (prefix, path) => format!("{prefix}.{path}"),
+    }
+}
+
+/// Reads `value` as a `T`, reporting an error at its path below `prefix`.
+fn read_at<T: DeserializeOwned>(value: &Value, lossless: bool, prefix: &str) -> Result<T, Problem> {
+    let result = match lossless {
+        true => serde_path_to_error::deserialize(LosslessDeserializer(value)),
+        false => serde_path_to_error::deserialize(value),
+    };
+    result.map_err(|e| Problem {
+        path: join(prefix, &e.path().to_string()),
+        message: e.into_inner().to_string(),
+    })
+}
+
+fn locate_item(item: &Value, lossless: bool, path: &str) -> Option<Problem> {
+    if let Err(problem) = read_at::<ItemFields>(item, lossless, path) {
+        return Some(problem);
+    }
+    let object = item.as_object()?;
+    if !TINYRICK_FIELDS
+        .iter()
+        .any(|field| object.contains_key(*field))
+    {
+        return None;
+    }
+    // the item's own fields are ignored
+    read_at::<TinyRick>(item, lossless, &format!("{path}.tinyrick")).err()
+}
+
+/// Every part of the save that can't be read, at most one per item.
+fn locate(json: &Value, lossless: bool) -> Vec<Problem> {
+    let mut problems = vec![];
+    if let Err(problem) = read_at::<Outline>(json, lossless, "") {
+        problems.push(problem);
+    }
+    if let Some(Value::Array(items)) = json.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            problems.extend(locate_item(item, lossless, &format!("items[{i}]")));
+        }
+    }
+    problems
+}
+
+/// The tag of `{"Tag": ...}`.
+fn tag(value: &Value) -> Option<&str> {
+    match value {
+        Value::Object(object) if object.len() == 1 => object.keys().next().map(|k| k.as_str()),
+        _ => None,
+    }
+}
+
+/// The tag of `{"Tag": ...}`, or the string itself.
+fn type_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(name) => Some(name),
+        value => tag(value),
+    }
+}
+
+/// The tag uesave writes values of a property type with, `Int` for `IntProperty`.
+fn property_tag(declared: &str) -> &str {
+    declared.strip_suffix("Property").unwrap_or(declared)
+}
+
+/// The tag of the values in an array or set, `{"Base": {"Int": [...]}}` holds `Int`.
+fn element_tag(value: &Value) -> Option<&str> {
+    match tag(value)? {
+        "Base" => tag(&value["Base"]),
+        found => Some(found),
+    }
+}
+
+fn mismatch(path: String, declared: &str, found: &str, problems: &mut Vec<Problem>) {
+    problems.push(Problem {
+        path,
+        message: format!("declared as {declared} but holds a {found}"),
+    });
+}
+
+/// Arrays, sets and maps whose values aren't the type they declare.
+fn check_container_types(object: &Map<String, Value>, path: &str, problems: &mut Vec<Problem>) {
+    let Some(value) = object.get("value") else {
+        return;
+    };
+    for field in ["array_type", "set_type"] {
+        if let (Some(declared), Some(found)) = (
+            object.get(field).and_then(Value::as_str),
+            element_tag(value),
+        ) {
+            if property_tag(declared) != found {
+                mismatch(format!("{path}.value"), declared, found, problems);
+            }
+        }
+    }
+    let Some(entries) = value.as_array() else {
+        return;
+    };
+    for (field, part) in [("key_type", "key"), ("value_type", "value")] {
+        let Some(declared) = object.get(field).and_then(Value::as_str) else {
+            continue;
+        };
+        for (i, entry) in entries.iter().enumerate() {
+            if let Some(found) = entry.get(part).and_then(tag) {
+                if property_tag(declared) != found {
+                    mismatch(
+                        format!("{path}.value[{i}].{part}"),
+                        declared,
+                        found,
+                        problems,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The enum of `Enum::Value`, if it names one.
+fn enum_of(value: &str) -> Option<&str> {
+    value.split_once("::").map(|(name, _)| name)
+}
+
+/// Enum and byte values that don't match their `enum_type`.
+///
+/// A byte is a number when its `enum_type` is `None` and a label of that enum otherwise.
+fn check_enum(kind: &str, inner: &Value, path: &str, problems: &mut Vec<Problem>) {
+    let (Some(declared), Some(value)) = (
+        inner.get("enum_type").and_then(Value::as_str),
+        inner.get("value"),
+    ) else {
+        return;
+    };
+    let path = format!("{path}.value");
+    let label = match (kind, value) {
+        ("Enum", Value::String(label)) => label.as_str(),
+        ("Byte", value) => match (declared, tag(value)) {
+            ("None", Some("Label")) => {
+                return mismatch(path, "a plain byte", "label", problems);
+            }
+            ("None", _) => return,
+            (_, Some("Byte")) => return mismatch(path, declared, "number", problems),
+            _ => match value.get("Label").and_then(Value::as_str) {
+                Some(label) => label,
+                None => return,
+            },
+        },
+        _ => return,
+    };
+    if let Some(found) = enum_of(label) {
+        if found != declared {
+            mismatch(path, declared, found, problems);
+        }
+    }
+}
+
+/// Values that don't match the type they declare: structs, the elements of arrays, sets
+/// and maps, and enums.
+fn check_types(value: &Value, path: &str, problems: &mut Vec<Problem>) {
+    match value {
+        Value::Object(object) => {
+            if let (Some(declared), Some(value)) = (object.get("struct_type"), object.get("value"))
+            {
+                if let (Some(declared), Some(found)) = (type_name(declared), type_name(value)) {
+                    if declared != found {
+                        problems.push(Problem {
+                            path: format!("{path}.value"),
+                            message: format!("declared as a {declared} struct but holds a {found}"),
+                        });
+                    }
+                }
+            }
+            check_container_types(object, path, problems);
+            if let Some(kind @ ("Enum" | "Byte")) = tag(value) {
+                check_enum(kind, &value[kind], &join(path, kind), problems);
+            }
+            for (key, value) in object {
+                check_types(value, &join(path, key), problems);
+            }
+        }
+        Value::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                check_types(value, &format!("{path}[{i}]"), problems);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_format_version(value: &Value, path: &str, problems: &mut Vec<Problem>) {
+    if value.get("format_version").and_then(Value::as_u64) == Some(0) {
+        problems.push(Problem {
+            path: join(path, "format_version"),
+            message: "0 isn't a version any save is written with".to_string(),
+        });
+    }
+}
+
+fn check_unreal_version(value: &Value, path: &str, problems: &mut Vec<Problem>) {
+    let Some(version) = value.get("unreal_version").and_then(Value::as_u64) else {
+        return;
+    };
+    if !UNREAL_VERSIONS.iter().any(|range| range.contains(&version)) {
+        problems.push(Problem {
+            path: join(path, "unreal_version"),
+            message: format!(
+                "{version} isn't an Unreal Engine package version, \
+                 expected 214 to 522 for UE4 or 1000 to 1012 for UE5"
+            ),
+        });
+    }
+}
+
+/// Problems with a save that reads fine: reused guids, impossible versions, and values
+/// that don't match their declared type.
+pub fn check(json: &Value) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    if let Some(header) = json.get("header") {
+        check_format_version(header, "header", &mut problems);
+        check_unreal_version(header, "header", &mut problems);
+        let format_version = header.get("format_version").and_then(Value::as_u64);
+        let groups = json.get("groups").and_then(Value::as_array);
+        if let (Some(format_version), Some(groups)) = (format_version, groups) {
+            if format_version < GROUPS_FORMAT_VERSION as u64 && !groups.is_empty() {
+                problems.push(Problem {
+                    path: "groups".to_string(),
+                    message: format!(
+                        "saves with format version {format_version} don't read groups back, \
+                         they need {GROUPS_FORMAT_VERSION} or later"
+                    ),
+                });
+            }
+        }
+    }
+
+    let mut guids: HashMap<String, usize> = HashMap::new();
+    if let Some(Value::Array(items)) = json.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            let path = format!("items[{i}]");
+            if let Some(guid) = item.get("guid").and_then(Value::as_str) {
+                let first = *guids.entry(guid.to_ascii_lowercase()).or_insert(i);
+                if first != i {
+                    problems.push(Problem {
+                        path: format!("{path}.guid"),
+                        message: format!("{guid} is already used by items[{first}]"),
+                    });
+                }
+            }
+            let tinyrick = format!("{path}.tinyrick");
+            check_format_version(item, &tinyrick, &mut problems);
+            check_unreal_version(item, &tinyrick, &mut problems);
+            if let Some(properties) = item.get("properties") {
+                check_types(properties, &format!("{tinyrick}.properties"), &mut problems);
+            }
+            if let Some(actors) = item.get("actors") {
+                check_types(actors, &format!("{tinyrick}.actors"), &mut problems);
+            }
+        }
+    }
+    if let Some(lists) = json.get("properties") {
+        check_types(lists, "properties", &mut problems);
+    }
+    problems
+}
+
+/// Reads the json of a save, `lossless` as written by `to-json --lossless`.
+///
+/// The save is read once, the parts are only read on their own to find what went wrong.
+pub fn from_value(json: Value, lossless: bool) -> Result<SuiteBro, Problems> {
+    let save = read_at::<SuiteBro>(&json, lossless, "").map_err(|problem| {
+        let problems = locate(&json, lossless);
+        match problems.is_empty() {
+            // anything the parts missed
+            true => Problems(vec![problem]),
+            false => Problems(problems),
+        }
+    })?;
+    let problems = check(&json);
+    if !problems.is_empty() {
+        return Err(Problems(problems));
+    }
+    Ok(save)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::one_item;
+    use serde_json::json;
+
+    fn fixture() -> anyhow::Result<(SuiteBro, Value)> {
+        let save = one_item();
+        let json = serde_json::to_value(&save)?;
+        Ok((save, json))
+    }
+
+    fn paths(result: Result<SuiteBro, Problems>) -> Vec<String> {
+        result.unwrap_err().0.into_iter().map(|p| p.path).collect()
+    }
+
+    #[test]
+    fn test_valid() -> anyhow::Result<()> {
+        let (save, json) = fixture()?;
+        assert_eq!(from_value(json, false)?, save);
+        assert_eq!(from_value(crate::lossless::to_value(&save)?, true)?, save);
+        Ok(())
+    }
+
+    #[test]
+    fn test_paths() -> anyhow::Result<()> {
+        let (_, json) = fixture()?;
+
+        let mut broken = json.clone();
+        broken["items"][0]["scale"]["y"] = "big".into();
+        let problems = from_value(broken, false).unwrap_err();
+        assert_eq!(problems.0[0].path, "items[0].scale.y");
+        assert!(problems.0[0].message.contains("expected f32"));
+
+        let mut broken = json.clone();
+        let name = broken["items"][0]["properties"]
+            .as_object()
+            .and_then(|properties| properties.keys().next().cloned())
+            .expect("the fixture has properties");
+        broken["items"][0]["properties"][&name] = json!({ "NotAProperty": 1 });
+        let problems = paths(from_value(broken, false));
+        assert!(problems[0].starts_with(&format!("items[0].tinyrick.properties.{name}")));
+
+        let mut broken = json;
+        broken["header"]["unreal_version"] = json!(-1);
+        assert_eq!(paths(from_value(broken, false)), ["header.unreal_version"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checks() -> anyhow::Result<()> {
+        let (_, mut json) = fixture()?;
+        let item = json["items"][0].clone();
+        json["items"].as_array_mut().unwrap().push(item);
+        json["header"]["format_version"] = json!(0);
+        json["items"][1]["properties"]["Tint"] = json!({ "Struct": {
+            "id": null,
+            "value": { "Color": { "r": 1, "g": 2, "b": 3, "a": 4 } },
+            "struct_type": "LinearColor",
+            "struct_id": "00000000-0000-0000-0000-000000000000",
+        } });
+
+        let problems: Vec<_> = check(&json).into_iter().map(|p| p.path).collect();
+        assert_eq!(
+            problems,
+            [
+                "header.format_version",
+                "items[1].guid",
+                "items[1].tinyrick.properties.Tint.Struct.value",
+            ]
+        );
+        assert!(from_value(json, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_declared_types() -> anyhow::Result<()> {
+        let (_, mut json) = fixture()?;
+        let properties = &mut json["items"][0]["properties"];
+        properties["Scores"] = json!({ "Array": {
+            "array_type": "IntProperty",
+            "id": null,
+            "value": { "Base": { "Str": ["high"] } },
+        } });
+        properties["Tags"] = json!({ "Set": {
+            "set_type": "NameProperty",
+            "id": null,
+            "value": { "Base": { "Name": ["Seat"] } },
+        } });
+        properties["Counts"] = json!({ "Map": {
+            "key_type": "StrProperty",
+            "value_type": "IntProperty",
+            "id": null,
+            "value": [
+                { "key": { "Str": "Chair" }, "value": { "Int": 1 } },
+                { "key": { "Int": 2 }, "value": { "Int": 1 } },
+            ],
+        } });
+        properties["Mode"] = json!({ "Enum": {
+            "id": null,
+            "enum_type": "EMode",
+            "value": "EOther::On",
+        } });
+        properties["Level"] = json!({ "Byte": {
+            "id": null,
+            "enum_type": "None",
+            "value": { "Label": "EMode::On" },
+        } });
+        properties["Setting"] = json!({ "Byte": {
+            "id": null,
+            "enum_type": "EMode",
+            "value": { "Label": "EMode::On" },
+        } });
+
+        let problems: Vec<_> = check(&json).into_iter().map(|p| p.path).collect();
+        let properties = "items[0].tinyrick.properties";
+        assert_eq!(
+            problems,
+            [
+                format!("{properties}.Scores.Array.value"),
+                format!("{properties}.Counts.Map.value[1].key"),
+                format!("{properties}.Mode.Enum.value"),
+                format!("{properties}.Level.Byte.value"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_versions() -> anyhow::Result<()> {
+        let (_, mut json) = fixture()?;
+        json["header"]["unreal_version"] = json!(99);
+        json["items"][0]["unreal_version"] = json!(2000);
+        let problems: Vec<_> = check(&json).into_iter().map(|p| p.path).collect();
+        assert_eq!(
+            problems,
+            ["header.unreal_version", "items[0].tinyrick.unreal_version"]
+        );
+
+        json["header"]["unreal_version"] = json!(522);
+        json["items"][0]["unreal_version"] = json!(1005);
+        assert!(check(&json).is_empty());
+        Ok(())
+    }
+}
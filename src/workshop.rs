@@ -0,0 +1,336 @@
+//! Steam workshop items a save depends on, and whether they are installed locally.
+
+use std::fmt;
+use std::fs;
+use std::num::NonZeroU64;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+
+use crate::suitebro::SuiteBro;
+
+/// Steam app id of Tower Unite.
+pub const TOWER_UNITE_APP_ID: u32 = 394690;
+
+/// Id of a steam workshop item. A `steam_item_id` of 0 means the item isn't from the workshop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WorkshopId(NonZeroU64);
+
+impl WorkshopId {
+    pub fn new(steam_item_id: u64) -> Option<Self> {
+        NonZeroU64::new(steam_item_id).map(WorkshopId)
+    }
+
+    pub fn get(self) -> u64 {
+        self.0.get()
+    }
+
+    pub fn url(self) -> String {
+        format!(
+            "https://steamcommunity.com/sharedfiles/filedetails/?id={}",
+            self.0
+        )
+    }
+}
+
+impl fmt::Display for WorkshopId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for WorkshopId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        WorkshopId::new(s.parse()?).ok_or_else(|| anyhow!("0 is not a workshop id"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Dependency {
+    pub id: WorkshopId,
+    /// number of items using it
+    pub uses: usize,
+    /// class names of the items using it
+    pub classes: IndexSet<String>,
+}
+
+/// Every workshop item used in the save, most used first.
+pub fn dependencies(save: &SuiteBro) -> Vec<Dependency> {
+    let mut dependencies: IndexMap<WorkshopId, Dependency> = IndexMap::new();
+    for item in &save.items {
+        let Some(id) = item.workshop_id() else {
+            continue;
+        };
+        let dependency = dependencies.entry(id).or_insert_with(|| Dependency {
+            id,
+            uses: 0,
+            classes: IndexSet::new(),
+        });
+        dependency.uses += 1;
+        dependency.classes.insert(item.name.clone());
+    }
+    let mut dependencies: Vec<_> = dependencies.into_values().collect();
+    dependencies.sort_by(|a, b| b.uses.cmp(&a.uses));
+    dependencies
+}
+
+/// A value in a valve key values (`.acf`/`.vdf`) file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vdf {
+    Str(String),
+    Map(IndexMap<String, Vdf>),
+}
+
+impl Vdf {
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Map(map) => map
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            Vdf::Str(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Vdf::Str(s) => Some(s),
+            Vdf::Map(_) => None,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let map = match self {
+            Vdf::Map(map) => Some(map),
+            Vdf::Str(_) => None,
+        };
+        map.into_iter()
+            .flat_map(|map| map.keys().map(String::as_str))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c) => s.push(c),
+                            None => bail!("unterminated string"),
+                        },
+                        Some(c) => s.push(c),
+                        None => bail!("unterminated string"),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            c if c.is_whitespace() => {}
+            c => {
+                let mut s = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}\"".contains(*c)) {
+                    s.push(c);
+                }
+                tokens.push(Token::Str(s));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_map(tokens: &mut std::vec::IntoIter<Token>, nested: bool) -> anyhow::Result<Vdf> {
+    let mut map = IndexMap::new();
+    loop {
+        let key = match tokens.next() {
+            Some(Token::Str(key)) => key,
+            Some(Token::Close) if nested => break,
+            None if !nested => break,
+            token => bail!("expected a key, found {token:?}"),
+        };
+        let value = match tokens.next() {
+            Some(Token::Str(value)) => Vdf::Str(value),
+            Some(Token::Open) => parse_map(tokens, true)?,
+            token => bail!("expected a value for `{key}`, found {token:?}"),
+        };
+        map.insert(key, value);
+    }
+    Ok(Vdf::Map(map))
+}
+
+pub fn parse_vdf(input: &str) -> anyhow::Result<Vdf> {
+    parse_map(&mut tokenize(input)?.into_iter(), false)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstalledWorkshop {
+    /// items listed under `WorkshopItemsInstalled` in the acf manifest
+    pub in_manifest: IndexSet<WorkshopId>,
+    /// items with a directory under `content/<app id>`
+    pub on_disk: IndexSet<WorkshopId>,
+}
+
+impl InstalledWorkshop {
+    /// Reads a steam `steamapps/workshop` directory.
+    pub fn read(workshop_dir: &Path, app_id: u32) -> anyhow::Result<Self> {
+        let mut installed = InstalledWorkshop::default();
+
+        let manifest_path = workshop_dir.join(format!("appworkshop_{app_id}.acf"));
+        let manifest = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("reading {}", manifest_path.display()))?;
+        let manifest =
+            parse_vdf(&manifest).with_context(|| format!("parsing {}", manifest_path.display()))?;
+        if let Some(items) = manifest
+            .get("AppWorkshop")
+            .and_then(|w| w.get("WorkshopItemsInstalled"))
+        {
+            installed.in_manifest = items.keys().filter_map(|k| k.parse().ok()).collect();
+        }
+
+        let content_dir = workshop_dir.join("content").join(app_id.to_string());
+        if content_dir.is_dir() {
+            for entry in fs::read_dir(&content_dir)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                if let Some(id) = entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                    installed.on_disk.insert(id);
+                }
+            }
+        }
+        Ok(installed)
+    }
+
+    pub fn is_installed(&self, id: WorkshopId) -> bool {
+        self.in_manifest.contains(&id) && self.on_disk.contains(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{item, save};
+
+    #[test]
+    fn test_parse_acf() {
+        let acf = r#"
+"AppWorkshop"
+{
+	"appid"		"394690"
+	"WorkshopItemsInstalled"
+	{
+		"1234"
+		{
+			"size"		"1024"
+			"timeupdated"		"1700000000"
+		}
+		"5678"
+		{
+			"size"		"2048"
+		}
+	}
+}
+"#;
+        let vdf = parse_vdf(acf).unwrap();
+        let workshop = vdf.get("AppWorkshop").unwrap();
+        assert_eq!(workshop.get("appid").and_then(Vdf::as_str), Some("394690"));
+        let installed: Vec<_> = workshop
+            .get("WorkshopItemsInstalled")
+            .unwrap()
+            .keys()
+            .collect();
+        assert_eq!(installed, ["1234", "5678"]);
+        assert!(parse_vdf("\"a\" {").is_err());
+    }
+
+    #[test]
+    fn test_workshop_id() {
+        assert_eq!(WorkshopId::new(0), None);
+        assert_eq!("1234".parse::<WorkshopId>().unwrap().get(), 1234);
+        assert!("0".parse::<WorkshopId>().is_err());
+    }
+
+    fn id(id: u64) -> WorkshopId {
+        WorkshopId::new(id).unwrap()
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let items = [
+            ("Chair", 7),
+            ("Table", 5),
+            ("Chair", 5),
+            ("Lamp", 0),
+            ("Sofa", 7),
+            ("Rug", 9),
+        ]
+        .map(|(name, steam_item_id)| {
+            let mut item = item(name);
+            item.steam_item_id = steam_item_id;
+            item
+        });
+        let dependencies = dependencies(&save(items.into()));
+        let found: Vec<_> = dependencies.iter().map(|d| (d.id.get(), d.uses)).collect();
+        // most used first, ties in the order they appear, the lamp has no id
+        assert_eq!(found, [(7, 2), (5, 2), (9, 1)]);
+        let classes: Vec<_> = dependencies[0].classes.iter().collect();
+        assert_eq!(classes, ["Chair", "Sofa"]);
+        let classes: Vec<_> = dependencies[1].classes.iter().collect();
+        assert_eq!(classes, ["Table", "Chair"]);
+    }
+
+    #[test]
+    fn test_read_installed() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("suitebro-workshop-{}", std::process::id()));
+        let content = dir.join("content").join(TOWER_UNITE_APP_ID.to_string());
+        // 3 is listed but not downloaded, 4 is downloaded but not listed
+        for folder in ["1", "2", "4"] {
+            fs::create_dir_all(content.join(folder))?;
+        }
+        fs::write(content.join("5"), "not a folder")?;
+        let acf = r#""AppWorkshop" { "WorkshopItemsInstalled" { "1" {} "2" {} "3" {} } }"#;
+        fs::write(
+            dir.join(format!("appworkshop_{TOWER_UNITE_APP_ID}.acf")),
+            acf,
+        )?;
+
+        let installed = InstalledWorkshop::read(&dir, TOWER_UNITE_APP_ID);
+        let missing = InstalledWorkshop::read(&dir, 1);
+        fs::remove_dir_all(&dir)?;
+        let installed = installed?;
+
+        assert_eq!(installed.in_manifest, IndexSet::from([id(1), id(2), id(3)]));
+        let mut on_disk: Vec<_> = installed.on_disk.iter().map(|id| id.get()).collect();
+        on_disk.sort();
+        assert_eq!(on_disk, [1, 2, 4]);
+        assert!(installed.is_installed(id(1)));
+        assert!(!installed.is_installed(id(3)));
+        assert!(!installed.is_installed(id(4)));
+        assert!(!installed.is_installed(id(5)));
+        // no manifest for that app
+        assert!(missing.is_err());
+        Ok(())
+    }
+}
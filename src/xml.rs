@@ -0,0 +1,342 @@
+//! Xml for tools that want one.
+//!
+//! Items are `<item>` elements with their transform as attributes, `rotation="x y z w"`
+//! (or `"pitch yaw roll"` after `euler`), `position` and `scale` as `"x y z"`. Xml has no
+//! good way to hold uesave's tagged properties, they are kept as json text.
+//!
+//! ```xml
+//! <suitebro format_version="1" unreal_version="517">
+//!   <item name="Chair" guid="..." steam_item_id="0" rotation="0 0 0 1" position="0 0 0" scale="1 1 1">
+//!     <tinyrick format_version="1" unreal_version="517">
+//!       <properties>{"Color":{...}}</properties>
+//!       <actors>[]</actors>
+//!     </tinyrick>
+//!   </item>
+//!   <property_list name="...">
+//!     <properties>{...}</properties>
+//!   </property_list>
+//!   <group item_count="2" group_id="1"/>
+//! </suitebro>
+//! ```
+//!
+//! Like the other text formats in `convert` this goes to and from the json of a save.
+
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "suitebro")]
+struct XmlSave {
+    #[serde(rename = "@format_version")]
+    format_version: u32,
+    #[serde(rename = "@unreal_version")]
+    unreal_version: u32,
+    #[serde(rename = "item", default)]
+    items: Vec<XmlItem>,
+    #[serde(rename = "property_list", default)]
+    properties: Vec<XmlPropertyList>,
+    #[serde(rename = "group", default)]
+    groups: Vec<XmlGroup>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct XmlItem {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@guid")]
+    guid: String,
+    #[serde(rename = "@steam_item_id")]
+    steam_item_id: u64,
+    #[serde(rename = "@rotation")]
+    rotation: String,
+    #[serde(rename = "@position")]
+    position: String,
+    #[serde(rename = "@scale")]
+    scale: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tinyrick: Option<XmlTinyRick>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct XmlTinyRick {
+    #[serde(rename = "@format_version")]
+    format_version: u32,
+    #[serde(rename = "@unreal_version")]
+    unreal_version: u32,
+    /// json
+    properties: String,
+    /// json
+    actors: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct XmlPropertyList {
+    #[serde(rename = "@name")]
+    name: String,
+    /// json
+    properties: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct XmlGroup {
+    #[serde(rename = "@item_count")]
+    item_count: u32,
+    #[serde(rename = "@group_id")]
+    group_id: u32,
+}
+
+fn field<'a>(value: &'a Value, name: &str, path: &str) -> anyhow::Result<&'a Value> {
+    value
+        .get(name)
+        .ok_or_else(|| anyhow!("{path}: missing `{name}`"))
+}
+
+fn typed<T: serde::de::DeserializeOwned>(
+    value: &Value,
+    name: &str,
+    path: &str,
+) -> anyhow::Result<T> {
+    serde_json::from_value(field(value, name, path)?.clone())
+        .with_context(|| format!("{path}.{name}"))
+}
+
+/// `{"x": 1.0, "y": 2.0, "z": 3.0}` as `"1.0 2.0 3.0"`, lossless tags are kept as they are.
+///
+/// Components are looked up by name, so `{"w": 1.0, "x": 0.0, ...}` is still `"x y z w"`.
+fn components(value: &Value, path: &str) -> anyhow::Result<String> {
+    let Value::Object(object) = value else {
+        bail!("{path}: expected an object, found {value}");
+    };
+    let names: &[&str] = if object.contains_key("pitch") {
+        &["pitch", "yaw", "roll"]
+    } else if object.contains_key("w") {
+        &["x", "y", "z", "w"]
+    } else {
+        &["x", "y", "z"]
+    };
+    if let Some(extra) = object.keys().find(|key| !names.contains(&key.as_str())) {
+        bail!(
+            "{path}: unexpected `{extra}`, expected {}",
+            names.join(", ")
+        );
+    }
+    let parts = names
+        .iter()
+        .map(|name| match object.get(*name) {
+            Some(Value::Number(number)) => Ok(number.to_string()),
+            Some(Value::String(tag)) => Ok(tag.clone()),
+            Some(part) => Err(anyhow!("{path}.{name}: expected a number, found {part}")),
+            None => Err(anyhow!("{path}: missing `{name}`")),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(parts.join(" "))
+}
+
+fn parse_components(text: &str, path: &str) -> anyhow::Result<Value> {
+    let parts: Vec<Value> = text
+        .split_whitespace()
+        .map(|part| match serde_json::from_str(part) {
+            Ok(Value::Number(number)) => Value::Number(number),
+            _ => Value::String(part.to_string()),
+        })
+        .collect();
+    let names: &[&str] = match parts.len() {
+        3 if path.ends_with("rotation") => &["pitch", "yaw", "roll"],
+        3 => &["x", "y", "z"],
+        4 => &["x", "y", "z", "w"],
+        n => bail!("{path}: expected 3 or 4 numbers, found {n}"),
+    };
+    Ok(Value::Object(
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .zip(parts)
+            .collect(),
+    ))
+}
+
+fn item_to_xml(item: &Value, path: &str) -> anyhow::Result<XmlItem> {
+    let tinyrick = match item.get("properties") {
+        Some(properties) => Some(XmlTinyRick {
+            format_version: typed(item, "format_version", path)?,
+            unreal_version: typed(item, "unreal_version", path)?,
+            properties: properties.to_string(),
+            actors: field(item, "actors", path)?.to_string(),
+        }),
+        None => None,
+    };
+    Ok(XmlItem {
+        name: typed(item, "name", path)?,
+        guid: typed(item, "guid", path)?,
+        steam_item_id: typed(item, "steam_item_id", path)?,
+        rotation: components(field(item, "rotation", path)?, &format!("{path}.rotation"))?,
+        position: components(field(item, "position", path)?, &format!("{path}.position"))?,
+        scale: components(field(item, "scale", path)?, &format!("{path}.scale"))?,
+        tinyrick,
+    })
+}
+
+fn item_from_xml(item: XmlItem, path: &str) -> anyhow::Result<Value> {
+    let mut object = Map::new();
+    object.insert("name".into(), item.name.into());
+    object.insert("guid".into(), item.guid.into());
+    object.insert("steam_item_id".into(), item.steam_item_id.into());
+    if let Some(tinyrick) = item.tinyrick {
+        object.insert("format_version".into(), tinyrick.format_version.into());
+        object.insert("unreal_version".into(), tinyrick.unreal_version.into());
+        let properties = serde_json::from_str(&tinyrick.properties)
+            .with_context(|| format!("{path}.tinyrick.properties"))?;
+        object.insert("properties".into(), properties);
+        let actors = serde_json::from_str(&tinyrick.actors)
+            .with_context(|| format!("{path}.tinyrick.actors"))?;
+        object.insert("actors".into(), actors);
+    }
+    for (name, text) in [
+        ("rotation", item.rotation),
+        ("position", item.position),
+        ("scale", item.scale),
+    ] {
+        let value = parse_components(&text, &format!("{path}.{name}"))?;
+        object.insert(name.into(), value);
+    }
+    Ok(Value::Object(object))
+}
+
+/// Writes the json of a save as xml.
+pub fn to_xml(json: &Value) -> anyhow::Result<String> {
+    let header = field(json, "header", "save")?;
+    let items = field(json, "items", "save")?
+        .as_array()
+        .context("items: expected an array")?;
+    let lists = field(json, "properties", "save")?
+        .as_array()
+        .context("properties: expected an array")?;
+
+    let save = XmlSave {
+        format_version: typed(header, "format_version", "header")?,
+        unreal_version: typed(header, "unreal_version", "header")?,
+        items: items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| item_to_xml(item, &format!("items[{i}]")))
+            .collect::<anyhow::Result<_>>()?,
+        properties: lists
+            .iter()
+            .enumerate()
+            .map(|(i, list)| {
+                let path = format!("properties[{i}]");
+                Ok(XmlPropertyList {
+                    name: typed(list, "name", &path)?,
+                    properties: field(list, "properties", &path)?.to_string(),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+        groups: field(json, "groups", "save")?
+            .as_array()
+            .context("groups: expected an array")?
+            .iter()
+            .enumerate()
+            .map(|(i, group)| {
+                let path = format!("groups[{i}]");
+                Ok(XmlGroup {
+                    item_count: typed(group, "item_count", &path)?,
+                    group_id: typed(group, "group_id", &path)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?,
+    };
+
+    let mut xml = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+    serializer.indent(' ', 2);
+    save.serialize(serializer)?;
+    xml.push('\n');
+    Ok(xml)
+}
+
+/// Reads xml written by [`to_xml`] back into the json of a save.
+pub fn from_xml(xml: &str) -> anyhow::Result<Value> {
+    let save: XmlSave = quick_xml::de::from_str(xml)?;
+    let items = save
+        .items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| item_from_xml(item, &format!("items[{i}]")))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let properties = save
+        .properties
+        .into_iter()
+        .enumerate()
+        .map(|(i, list)| {
+            let properties: Value = serde_json::from_str(&list.properties)
+                .with_context(|| format!("properties[{i}].properties"))?;
+            Ok(json!({ "name": list.name, "properties": properties }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let groups: Vec<Value> = save
+        .groups
+        .into_iter()
+        .map(|group| json!({ "item_count": group.item_count, "group_id": group.group_id }))
+        .collect();
+    Ok(json!({
+        "header": {
+            "format_version": save.format_version,
+            "unreal_version": save.unreal_version,
+        },
+        "items": items,
+        "properties": properties,
+        "groups": groups,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suitebro::tests::{one_item, ONE_ITEM};
+    use crate::suitebro::{get_tower_types, SuiteBro};
+    use std::io::Cursor;
+    use uesave::Writable;
+
+    #[test]
+    fn test_round_trip() -> anyhow::Result<()> {
+        let save = one_item();
+
+        let xml = to_xml(&serde_json::to_value(&save)?)?;
+        assert!(xml.starts_with("<suitebro "));
+        assert!(xml.contains(" rotation=\""));
+        let read: SuiteBro = serde_json::from_value(from_xml(&xml)?)?;
+        assert_eq!(read, save);
+
+        // and back to the same bytes
+        let mut output = vec![];
+        uesave::Context::run_with_types(
+            &get_tower_types(),
+            &mut Cursor::new(&mut output),
+            |ctx| read.write(ctx),
+        )?;
+        assert_eq!(output, ONE_ITEM);
+        Ok(())
+    }
+
+    #[test]
+    fn test_components() -> anyhow::Result<()> {
+        let rotation = json!({ "x": 0.5, "y": -0.5, "z": "#f32:7fc00000", "w": 1 });
+        let text = components(&rotation, "rotation")?;
+        assert_eq!(text, "0.5 -0.5 #f32:7fc00000 1");
+        assert_eq!(parse_components(&text, "items[0].rotation")?, rotation);
+
+        // not in the order they are written
+        let shuffled = json!({ "w": 1, "z": 0.25, "y": 0, "x": 0.5 });
+        assert_eq!(components(&shuffled, "rotation")?, "0.5 0 0.25 1");
+        let euler = json!({ "roll": 30, "pitch": 10, "yaw": 20 });
+        assert_eq!(components(&euler, "rotation")?, "10 20 30");
+        assert!(components(&json!({ "x": 1, "y": 2 }), "scale").is_err());
+        assert!(components(&json!({ "x": 1, "y": 2, "z": 3, "v": 4 }), "scale").is_err());
+
+        let euler = parse_components("10 20 30", "items[0].rotation")?;
+        assert_eq!(euler, json!({ "pitch": 10, "yaw": 20, "roll": 30 }));
+        assert!(parse_components("1 2", "items[0].scale").is_err());
+        Ok(())
+    }
+}